@@ -14,7 +14,7 @@ fn set_background_color() -> Result<()> {
 
     // Notice that the color values are stored in wAttribute.
     // So wee need to use bitwise operators to check if the values exists or to get current console colors.
-    let attrs = csbi.attributes();
+    let attrs = csbi.attributes().bits();
     let fg_color = attrs & 0x0007;
 
     // apply the blue background flag to the current attributes
@@ -36,14 +36,14 @@ fn set_foreground_color() -> Result<()> {
 
     // Notice that the color values are stored in wAttribute.
     // So we need to use bitwise operators to check if the values exists or to get current console colors.
-    let attrs = csbi.attributes();
+    let attrs = csbi.attributes().bits();
     let bg_color = attrs & 0x0070;
     let mut color = BLUE_FOREGROUND | bg_color;
 
     // background intensity is a separate value in attrs,
     // wee need to check if this was applied to the current bg color.
-    if (attrs & 0x0080 as u16) != 0 {
-        color = color | 0x0080 as u16;
+    if (attrs & 0x0080) != 0 {
+        color |= 0x0080;
     }
 
     // set the console text attribute to the new color value.
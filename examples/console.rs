@@ -1,5 +1,5 @@
 #[cfg(windows)]
-use std::io::Result;
+use crossterm_winapi::Result;
 
 #[cfg(windows)]
 use crossterm_winapi::ConsoleMode;
@@ -0,0 +1,22 @@
+#[cfg(windows)]
+use std::io::Result;
+
+#[cfg(windows)]
+use crossterm_winapi::LineEditor;
+
+#[cfg(windows)]
+fn main() -> Result<()> {
+    let mut editor = LineEditor::new();
+
+    // read lines until Ctrl+D is pressed on an empty line
+    while let Some(line) = editor.read_line("> ")? {
+        println!("you typed: {}", line);
+    }
+
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn main() {
+    println!("This example is for the Windows platform only.");
+}
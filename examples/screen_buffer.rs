@@ -1,7 +1,7 @@
 #![allow(dead_code)]
 
 #[cfg(windows)]
-use std::io::Result;
+use crossterm_winapi::Result;
 
 #[cfg(windows)]
 use crossterm_winapi::ScreenBuffer;
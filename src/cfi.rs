@@ -22,6 +22,12 @@ impl fmt::Debug for FontInfo {
     }
 }
 
+impl Default for FontInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl FontInfo {
     /// Create a new font info without all zeroed properties.
     pub fn new() -> FontInfo {
@@ -0,0 +1,195 @@
+#[cfg(windows)]
+use std::mem::{size_of, zeroed};
+
+#[cfg(windows)]
+use winapi::um::wincon::CONSOLE_FONT_INFOEX;
+
+#[cfg(any(windows, test))]
+use super::win32::LF_FACESIZE;
+use super::win32::TMPF_TRUETYPE;
+use super::Size;
+
+/// Extended information about the font of a console screen buffer, including its face name.
+///
+/// This is built from
+/// [`CONSOLE_FONT_INFOEX`](https://docs.microsoft.com/en-us/windows/console/console-font-infoex)
+/// and can be read with [`ScreenBuffer::font_info_ex`](crate::ScreenBuffer::font_info_ex) and
+/// written with [`ScreenBuffer::set_font_info_ex`](crate::ScreenBuffer::set_font_info_ex).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FontInfoEx {
+    /// The index of the font in the system's console font table.
+    pub index: u32,
+    /// The width and height of a character cell in pixels. When setting the font, a width of
+    /// zero lets the console pick the width that fits the height.
+    pub size: Size,
+    /// The pitch and family of the font, like the `tmPitchAndFamily` member of
+    /// [`TEXTMETRIC`](https://docs.microsoft.com/en-us/windows/win32/api/wingdi/ns-wingdi-textmetricw).
+    pub family: u32,
+    /// The weight of the font, from 100 to 1000, like
+    /// [`NORMAL_WEIGHT`](FontInfoEx::NORMAL_WEIGHT) or [`BOLD_WEIGHT`](FontInfoEx::BOLD_WEIGHT).
+    pub weight: u32,
+    /// The name of the typeface, like `Consolas`.
+    ///
+    /// It is cut off after 31 UTF-16 code units, or at a NUL character, when it is passed to
+    /// WinAPI.
+    pub face_name: String,
+}
+
+impl FontInfoEx {
+    /// The weight of a normal font, `FW_NORMAL`.
+    pub const NORMAL_WEIGHT: u32 = 400;
+    /// The weight of a bold font, `FW_BOLD`.
+    pub const BOLD_WEIGHT: u32 = 700;
+
+    /// Get whether the font is a TrueType font, rather than a raster font.
+    pub fn is_truetype(&self) -> bool {
+        self.family & TMPF_TRUETYPE != 0
+    }
+}
+
+/// Encode a face name as the NUL terminated UTF-16 string of `CONSOLE_FONT_INFOEX`, cutting it
+/// off at a NUL character or before the code unit that would leave no room for the terminating
+/// NUL. Surrogate pairs are never split.
+#[cfg(any(windows, test))]
+fn encode_face_name(name: &str) -> [u16; LF_FACESIZE] {
+    let mut face_name = [0; LF_FACESIZE];
+    let mut length = 0;
+    for c in name.chars().take_while(|&c| c != '\0') {
+        if length + c.len_utf16() >= LF_FACESIZE {
+            break;
+        }
+        length += c.encode_utf16(&mut face_name[length..]).len();
+    }
+    face_name
+}
+
+/// Decode the NUL terminated UTF-16 face name of `CONSOLE_FONT_INFOEX`, replacing unpaired
+/// surrogates with `U+FFFD`.
+#[cfg(any(windows, test))]
+fn decode_face_name(face_name: &[u16; LF_FACESIZE]) -> String {
+    let length = face_name
+        .iter()
+        .position(|&unit| unit == 0)
+        .unwrap_or(LF_FACESIZE);
+    String::from_utf16_lossy(&face_name[..length])
+}
+
+#[cfg(windows)]
+impl FontInfoEx {
+    /// Create a new, zeroed `CONSOLE_FONT_INFOEX` with its `cbSize` member set, as required by
+    /// `GetCurrentConsoleFontEx`.
+    pub(crate) fn raw() -> CONSOLE_FONT_INFOEX {
+        let mut cfi: CONSOLE_FONT_INFOEX = unsafe { zeroed() };
+        cfi.cbSize = size_of::<CONSOLE_FONT_INFOEX>() as u32;
+        cfi
+    }
+}
+
+#[cfg(windows)]
+impl From<CONSOLE_FONT_INFOEX> for FontInfoEx {
+    fn from(cfi: CONSOLE_FONT_INFOEX) -> Self {
+        FontInfoEx {
+            index: cfi.nFont,
+            size: Size::from(cfi.dwFontSize),
+            family: cfi.FontFamily,
+            weight: cfi.FontWeight,
+            face_name: decode_face_name(&cfi.FaceName),
+        }
+    }
+}
+
+#[cfg(windows)]
+impl From<&FontInfoEx> for CONSOLE_FONT_INFOEX {
+    fn from(info: &FontInfoEx) -> Self {
+        let mut cfi = FontInfoEx::raw();
+        cfi.nFont = info.index;
+        cfi.dwFontSize = info.size.into();
+        cfi.FontFamily = info.family;
+        cfi.FontWeight = info.weight;
+        cfi.FaceName = encode_face_name(&info.face_name);
+        cfi
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_face_name, encode_face_name, FontInfoEx, LF_FACESIZE};
+
+    #[test]
+    fn test_face_name_round_trip() {
+        for &name in &["", "Consolas", "Cascadia Mono", "ＭＳ ゴシック"] {
+            let face_name = encode_face_name(name);
+            assert_eq!(face_name[name.encode_utf16().count()], 0);
+            assert_eq!(decode_face_name(&face_name), name);
+        }
+    }
+
+    #[test]
+    fn test_face_name_truncation() {
+        // 31 code units fit next to the NUL character, 32 don't.
+        let fits = "a".repeat(LF_FACESIZE - 1);
+        assert_eq!(decode_face_name(&encode_face_name(&fits)), fits);
+        let long = "b".repeat(LF_FACESIZE);
+        let face_name = encode_face_name(&long);
+        assert_eq!(face_name[LF_FACESIZE - 1], 0);
+        assert_eq!(decode_face_name(&face_name), long[..LF_FACESIZE - 1]);
+
+        // A surrogate pair that would take the place of the NUL character is left out as a
+        // whole.
+        let name = format!("{}🦀", "c".repeat(LF_FACESIZE - 2));
+        assert_eq!(
+            decode_face_name(&encode_face_name(&name)),
+            "c".repeat(LF_FACESIZE - 2)
+        );
+
+        // The face name ends at a NUL character.
+        assert_eq!(
+            decode_face_name(&encode_face_name("Lucida\0Console")),
+            "Lucida"
+        );
+    }
+
+    #[test]
+    fn test_decode_face_name() {
+        // A face name that fills the array without a NUL character is taken as a whole.
+        let face_name = [u16::from(b'x'); LF_FACESIZE];
+        assert_eq!(decode_face_name(&face_name).len(), LF_FACESIZE);
+
+        let mut face_name = [0; LF_FACESIZE];
+        face_name[..3].copy_from_slice(&[u16::from(b'a'), 0xdc00, u16::from(b'b')]);
+        assert_eq!(decode_face_name(&face_name), "a\u{fffd}b");
+    }
+
+    #[test]
+    fn test_is_truetype() {
+        let mut info = FontInfoEx {
+            family: 0x36,
+            ..FontInfoEx::default()
+        };
+        assert!(info.is_truetype());
+        info.family = 0x30;
+        assert!(!info.is_truetype());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_font_info_ex_roundtrip() {
+        use std::mem::size_of;
+
+        use winapi::um::wincon::CONSOLE_FONT_INFOEX;
+
+        use crate::Size;
+
+        let info = FontInfoEx {
+            index: 7,
+            size: Size::new(0, 16),
+            family: 0x36,
+            weight: FontInfoEx::BOLD_WEIGHT,
+            face_name: "Cascadia Mono".to_string(),
+        };
+
+        let raw = CONSOLE_FONT_INFOEX::from(&info);
+        assert_eq!(raw.cbSize as usize, size_of::<CONSOLE_FONT_INFOEX>());
+        assert_eq!(FontInfoEx::from(raw), info);
+    }
+}
@@ -0,0 +1,309 @@
+//! This module contains [`CodePage`], the code pages the console uses to convert between the
+//! bytes of the ANSI functions and text, functions to get and set them, and [`CodePageGuard`],
+//! which restores them on drop.
+//!
+//! The console converts with a separate code page for input and for output. Bytes read or
+//! written in the OEM code pages 437 and 850 can be converted with [`CodePage::decode`] and
+//! [`CodePage::encode`] on every platform.
+
+use std::error::Error;
+use std::fmt;
+
+#[cfg(windows)]
+use winapi::um::{
+    consoleapi::{GetConsoleCP, GetConsoleOutputCP},
+    wincon::{SetConsoleCP, SetConsoleOutputCP},
+};
+
+#[cfg(windows)]
+use crate::handle::report_close_error;
+#[cfg(windows)]
+use crate::{result, Result};
+
+/// The identifier of a code page, like [`CodePage::UTF8`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct CodePage(u32);
+
+impl CodePage {
+    /// OEM United States, the default OEM code page of English versions of Windows.
+    pub const OEM_US: CodePage = CodePage(437);
+    /// OEM Multilingual Latin 1, the default OEM code page of many western European versions of
+    /// Windows.
+    pub const OEM_LATIN1: CodePage = CodePage(850);
+    /// ANSI Latin 1, the default ANSI code page of English and western European versions of
+    /// Windows.
+    pub const WINDOWS_1252: CodePage = CodePage(1252);
+    /// UTF-8.
+    pub const UTF8: CodePage = CodePage(65001);
+
+    /// Create a code page from its identifier.
+    pub const fn new(id: u32) -> CodePage {
+        CodePage(id)
+    }
+
+    /// Get the identifier of the code page.
+    pub const fn id(self) -> u32 {
+        self.0
+    }
+
+    /// Decode bytes in this code page, which has to be [`OEM_US`](CodePage::OEM_US) or
+    /// [`OEM_LATIN1`](CodePage::OEM_LATIN1).
+    pub fn decode(self, bytes: &[u8]) -> std::result::Result<String, CodecError> {
+        let table = self.table().ok_or(CodecError::Unsupported(self))?;
+        Ok(bytes
+            .iter()
+            .map(|&byte| match byte {
+                0..=0x7f => char::from(byte),
+                _ => table[usize::from(byte - 0x80)],
+            })
+            .collect())
+    }
+
+    /// Encode text in this code page, which has to be [`OEM_US`](CodePage::OEM_US) or
+    /// [`OEM_LATIN1`](CodePage::OEM_LATIN1).
+    pub fn encode(self, text: &str) -> std::result::Result<Vec<u8>, CodecError> {
+        let table = self.table().ok_or(CodecError::Unsupported(self))?;
+        text.chars()
+            .map(|c| {
+                if c.is_ascii() {
+                    return Ok(c as u8);
+                }
+                table
+                    .iter()
+                    .position(|&entry| entry == c)
+                    .map(|index| 0x80 + index as u8)
+                    .ok_or(CodecError::Unmappable(c))
+            })
+            .collect()
+    }
+
+    /// Get the characters of the bytes `0x80` to `0xff`, if the codec supports this code page.
+    fn table(self) -> Option<&'static [char; 128]> {
+        match self {
+            CodePage::OEM_US => Some(&CP437),
+            CodePage::OEM_LATIN1 => Some(&CP850),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CodePage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "code page {}", self.0)
+    }
+}
+
+impl From<u32> for CodePage {
+    fn from(id: u32) -> Self {
+        CodePage(id)
+    }
+}
+
+impl From<CodePage> for u32 {
+    fn from(code_page: CodePage) -> Self {
+        code_page.0
+    }
+}
+
+/// The reason text can not be converted with [`CodePage::decode`] or [`CodePage::encode`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CodecError {
+    /// The code page isn't supported by the codec.
+    Unsupported(CodePage),
+    /// The character doesn't exist in the code page.
+    Unmappable(char),
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CodecError::Unsupported(code_page) => {
+                write!(f, "{} is not supported by the codec", code_page)
+            }
+            CodecError::Unmappable(c) => {
+                write!(f, "character {:?} does not exist in the code page", c)
+            }
+        }
+    }
+}
+
+impl Error for CodecError {}
+
+/// The characters of the bytes `0x80` to `0xff` in code page 437.
+#[rustfmt::skip]
+const CP437: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ',
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»',
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐',
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧',
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀',
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩',
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}',
+];
+
+/// The characters of the bytes `0x80` to `0xff` in code page 850.
+#[rustfmt::skip]
+const CP850: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', 'ø', '£', 'Ø', '×', 'ƒ',
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '®', '¬', '½', '¼', '¡', '«', '»',
+    '░', '▒', '▓', '│', '┤', 'Á', 'Â', 'À', '©', '╣', '║', '╗', '╝', '¢', '¥', '┐',
+    '└', '┴', '┬', '├', '─', '┼', 'ã', 'Ã', '╚', '╔', '╩', '╦', '╠', '═', '╬', '¤',
+    'ð', 'Ð', 'Ê', 'Ë', 'È', 'ı', 'Í', 'Î', 'Ï', '┘', '┌', '█', '▄', '¦', 'Ì', '▀',
+    'Ó', 'ß', 'Ô', 'Ò', 'õ', 'Õ', 'µ', 'þ', 'Þ', 'Ú', 'Û', 'Ù', 'ý', 'Ý', '¯', '´',
+    '\u{ad}', '±', '‗', '¾', '¶', '§', '÷', '¸', '°', '¨', '·', '¹', '³', '²', '■', '\u{a0}',
+];
+
+/// Get the code page the console uses for input.
+///
+/// This wraps [`GetConsoleCP`](https://docs.microsoft.com/en-us/windows/console/getconsolecp).
+#[cfg(windows)]
+pub fn input_code_page() -> Result<CodePage> {
+    match unsafe { GetConsoleCP() } {
+        0 => Err(crate::Error::last_os_error("GetConsoleCP")),
+        id => Ok(CodePage(id)),
+    }
+}
+
+/// Set the code page the console uses for input.
+///
+/// This wraps [`SetConsoleCP`](https://docs.microsoft.com/en-us/windows/console/setconsolecp).
+#[cfg(windows)]
+pub fn set_input_code_page(code_page: CodePage) -> Result<()> {
+    result("SetConsoleCP", unsafe { SetConsoleCP(code_page.0) })
+        .map_err(|e| e.with_argument("code_page", &code_page.0))
+}
+
+/// Get the code page the console uses for output.
+///
+/// This wraps
+/// [`GetConsoleOutputCP`](https://docs.microsoft.com/en-us/windows/console/getconsoleoutputcp).
+#[cfg(windows)]
+pub fn output_code_page() -> Result<CodePage> {
+    match unsafe { GetConsoleOutputCP() } {
+        0 => Err(crate::Error::last_os_error("GetConsoleOutputCP")),
+        id => Ok(CodePage(id)),
+    }
+}
+
+/// Set the code page the console uses for output.
+///
+/// This wraps
+/// [`SetConsoleOutputCP`](https://docs.microsoft.com/en-us/windows/console/setconsoleoutputcp).
+#[cfg(windows)]
+pub fn set_output_code_page(code_page: CodePage) -> Result<()> {
+    result("SetConsoleOutputCP", unsafe {
+        SetConsoleOutputCP(code_page.0)
+    })
+    .map_err(|e| e.with_argument("code_page", &code_page.0))
+}
+
+/// Restores the input and output code page of the console to what they were when the guard was
+/// created, when it is dropped.
+///
+/// Errors of restoring the code pages while it is dropped are passed to the hook set with
+/// [`set_close_error_hook`](crate::set_close_error_hook).
+#[cfg(windows)]
+#[derive(Debug)]
+#[must_use = "the code pages are restored when the `CodePageGuard` is dropped"]
+pub struct CodePageGuard {
+    input: CodePage,
+    output: CodePage,
+}
+
+#[cfg(windows)]
+impl CodePageGuard {
+    /// Remember the current input and output code page, to restore them on drop.
+    pub fn new() -> Result<CodePageGuard> {
+        Ok(CodePageGuard {
+            input: input_code_page()?,
+            output: output_code_page()?,
+        })
+    }
+
+    /// Remember the current input and output code page, and switch both to `code_page`.
+    ///
+    /// If switching fails, the code pages are restored before the error is returned.
+    pub fn switch(code_page: CodePage) -> Result<CodePageGuard> {
+        let guard = CodePageGuard::new()?;
+        set_input_code_page(code_page)?;
+        set_output_code_page(code_page)?;
+        Ok(guard)
+    }
+
+    /// Get the input code page that is restored on drop.
+    pub fn input(&self) -> CodePage {
+        self.input
+    }
+
+    /// Get the output code page that is restored on drop.
+    pub fn output(&self) -> CodePage {
+        self.output
+    }
+}
+
+#[cfg(windows)]
+impl Drop for CodePageGuard {
+    fn drop(&mut self) {
+        if let Err(error) = set_input_code_page(self.input) {
+            report_close_error(&error);
+        }
+        if let Err(error) = set_output_code_page(self.output) {
+            report_close_error(&error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CodePage, CodecError};
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(CodePage::OEM_US.decode(b"dir C:\\").unwrap(), "dir C:\\");
+        assert_eq!(
+            CodePage::OEM_US
+                .decode(&[0x80, 0x9e, 0xc9, 0xcd, 0xbb, 0xe1, 0xff])
+                .unwrap(),
+            "Ç₧╔═╗ß\u{a0}"
+        );
+        assert_eq!(
+            CodePage::OEM_LATIN1
+                .decode(&[0x80, 0x9e, 0xb5, 0xd5, 0xf0])
+                .unwrap(),
+            "Ç×Áı\u{ad}"
+        );
+        assert_eq!(
+            CodePage::UTF8.decode(b"text"),
+            Err(CodecError::Unsupported(CodePage::UTF8))
+        );
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(
+            CodePage::OEM_US.encode("Größe ½").unwrap(),
+            b"Gr\x94\xe1e \xab"
+        );
+        assert_eq!(CodePage::OEM_LATIN1.encode("Ø©").unwrap(), b"\x9d\xb8");
+        assert_eq!(
+            CodePage::OEM_US.encode("Ø"),
+            Err(CodecError::Unmappable('Ø'))
+        );
+        assert_eq!(
+            CodePage::WINDOWS_1252.encode("a"),
+            Err(CodecError::Unsupported(CodePage::WINDOWS_1252))
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        for &code_page in &[CodePage::OEM_US, CodePage::OEM_LATIN1] {
+            let text = code_page.decode(&bytes).unwrap();
+            assert_eq!(text.chars().count(), 256);
+            assert_eq!(code_page.encode(&text).unwrap(), bytes);
+        }
+    }
+}
@@ -1,18 +1,37 @@
-use std::io::{self, Result};
+use std::io;
 use std::iter;
+use std::os::windows::io::{AsHandle, BorrowedHandle};
 use std::slice;
 use std::str;
+use std::time::Duration;
 
 use winapi::ctypes::c_void;
 use winapi::shared::minwindef::DWORD;
 use winapi::shared::ntdef::NULL;
 use winapi::um::consoleapi::{GetNumberOfConsoleInputEvents, ReadConsoleInputW, WriteConsoleW};
 use winapi::um::wincon::{
-    FillConsoleOutputAttribute, FillConsoleOutputCharacterA, GetLargestConsoleWindowSize,
-    SetConsoleTextAttribute, SetConsoleWindowInfo, COORD, INPUT_RECORD, SMALL_RECT,
+    FillConsoleOutputAttribute, FillConsoleOutputCharacterW, GetLargestConsoleWindowSize,
+    SetConsoleCursorPosition, SetConsoleTextAttribute, SetConsoleWindowInfo, COORD, INPUT_RECORD,
+    SMALL_RECT,
 };
 
-use super::{result, Coord, Handle, HandleType, InputRecord, WindowPositions};
+use super::utf8_writer::Encoded;
+use super::wait::wait_one;
+use super::{
+    encode_fill_char, result, wait_any, Coord, Error, ErrorKind, Handle, HandleType, InputRecord,
+    Result, Semaphore, WaitResult, Waitable, WindowPositions, WriteUtf16,
+};
+
+/// What ended a wait in [`Console::poll_with_semaphore`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Wakeup {
+    /// There are input events to read.
+    Input,
+    /// The semaphore was released.
+    Semaphore,
+    /// The timeout elapsed.
+    TimedOut,
+}
 
 /// A wrapper around a screen buffer.
 #[derive(Debug, Clone)]
@@ -39,8 +58,27 @@ impl Console {
     /// This wraps
     /// [`SetConsoleTextAttribute`](https://docs.microsoft.com/en-us/windows/console/setconsoletextattribute).
     pub fn set_text_attribute(&self, value: u16) -> Result<()> {
-        result(unsafe { SetConsoleTextAttribute(*self.handle, value) })?;
-        Ok(())
+        result("SetConsoleTextAttribute", unsafe {
+            SetConsoleTextAttribute(*self.handle, value)
+        })
+        .map_err(|e| {
+            e.with_handle(&self.handle)
+                .with_argument("attributes", &format_args!("{:#06x}", value))
+        })
+    }
+
+    /// Sets the cursor position in the console screen buffer.
+    ///
+    /// This wraps
+    /// [`SetConsoleCursorPosition`](https://docs.microsoft.com/en-us/windows/console/setconsolecursorposition).
+    pub fn set_cursor_position(&self, position: Coord) -> Result<()> {
+        result("SetConsoleCursorPosition", unsafe {
+            SetConsoleCursorPosition(*self.handle, COORD::from(position))
+        })
+        .map_err(|e| {
+            e.with_handle(&self.handle)
+                .with_argument("position", &position)
+        })
     }
 
     /// Sets the current size and position of a console screen buffer's window.
@@ -48,38 +86,51 @@ impl Console {
     /// This wraps
     /// [`SetConsoleWindowInfo`](https://docs.microsoft.com/en-us/windows/console/setconsolewindowinfo).
     pub fn set_console_info(&self, absolute: bool, rect: WindowPositions) -> Result<()> {
-        let absolute = match absolute {
-            true => 1,
-            false => 0,
-        };
         let a = SMALL_RECT::from(rect);
 
-        result(unsafe { SetConsoleWindowInfo(*self.handle, absolute, &a) })?;
-
-        Ok(())
+        result("SetConsoleWindowInfo", unsafe {
+            SetConsoleWindowInfo(*self.handle, i32::from(absolute), &a)
+        })
+        .map_err(|e| {
+            e.with_handle(&self.handle)
+                .with_argument("absolute", &absolute)
+                .with_argument("rect", &rect)
+        })
     }
 
     /// Writes a character to the console screen buffer a specified number of times, beginning at the specified coordinates.
     /// Returns the number of characters that have been written.
     ///
+    /// The character must lie in the Basic Multilingual Plane, because each console cell holds a
+    /// single UTF-16 code unit. Other characters, like most emoji, result in an error of kind
+    /// [`InvalidInput`](ErrorKind::InvalidInput); see [`encode_fill_char`].
+    ///
     /// This wraps
-    /// [`FillConsoleOutputCharacterA`](https://docs.microsoft.com/en-us/windows/console/fillconsoleoutputcharacter).
+    /// [`FillConsoleOutputCharacterW`](https://docs.microsoft.com/en-us/windows/console/fillconsoleoutputcharacter).
     pub fn fill_whit_character(
         &self,
         start_location: Coord,
         cells_to_write: u32,
         filling_char: char,
     ) -> Result<u32> {
+        let unit = encode_fill_char(filling_char)?;
+
         let mut chars_written = 0;
-        result(unsafe {
+        result("FillConsoleOutputCharacterW", unsafe {
             // fill the cells in console with blanks
-            FillConsoleOutputCharacterA(
+            FillConsoleOutputCharacterW(
                 *self.handle,
-                filling_char as i8,
+                unit,
                 cells_to_write,
                 COORD::from(start_location),
                 &mut chars_written,
             )
+        })
+        .map_err(|e| {
+            e.with_handle(&self.handle)
+                .with_argument("start_location", &start_location)
+                .with_argument("cells_to_write", &cells_to_write)
+                .with_argument("filling_char", &filling_char)
         })?;
 
         Ok(chars_written)
@@ -98,7 +149,7 @@ impl Console {
     ) -> Result<u32> {
         let mut cells_written = 0;
         // Get the position of the current console window
-        result(unsafe {
+        result("FillConsoleOutputAttribute", unsafe {
             FillConsoleOutputAttribute(
                 *self.handle,
                 dw_attribute,
@@ -106,6 +157,12 @@ impl Console {
                 COORD::from(start_location),
                 &mut cells_written,
             )
+        })
+        .map_err(|e| {
+            e.with_handle(&self.handle)
+                .with_argument("start_location", &start_location)
+                .with_argument("cells_to_write", &cells_to_write)
+                .with_argument("attributes", &format_args!("{:#06x}", dw_attribute))
         })?;
 
         Ok(cells_written)
@@ -115,41 +172,56 @@ impl Console {
     ///
     /// This wraps [`GetLargestConsoleWindowSize`](https://docs.microsoft.com/en-us/windows/console/getlargestconsolewindowsize)
     pub fn largest_window_size(&self) -> Result<Coord> {
-        crate::coord_result(unsafe { GetLargestConsoleWindowSize(*self.handle) })
+        crate::coord_result("GetLargestConsoleWindowSize", unsafe {
+            GetLargestConsoleWindowSize(*self.handle)
+        })
+        .map_err(|e| e.with_handle(&self.handle))
     }
 
     /// Writes a character string to a console screen buffer beginning at the current cursor location.
     ///
+    /// `buf` has to be valid UTF-8 as a whole. Returns the number of bytes of the characters that
+    /// were written; use [`Utf8Writer`](crate::Utf8Writer) to write a stream of bytes in which characters may be
+    /// split across calls.
+    ///
     /// This wraps
     /// [`WriteConsoleW`](https://docs.microsoft.com/en-us/windows/console/writeconsole).
     pub fn write_char_buffer(&self, buf: &[u8]) -> Result<usize> {
-        // get string from u8[] and parse it to an c_str
-        let utf8 = match str::from_utf8(buf) {
-            Ok(string) => string,
-            Err(_) => {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "Could not parse to utf8 string",
-                ));
-            }
-        };
-
-        let utf16: Vec<u16> = utf8.encode_utf16().collect();
-        let utf16_ptr: *const c_void = utf16.as_ptr() as *const _ as *const c_void;
-
-        let mut cells_written: u32 = 0;
-
-        result(unsafe {
+        let utf8 = str::from_utf8(buf)
+            .map_err(|e| Error::new("WriteConsoleW", ErrorKind::InvalidData, e))?;
+
+        let encoded =
+            Encoded::new(utf8.as_bytes(), false).map_err(|e| Error::from_io("WriteConsoleW", e))?;
+        let units_written = self.write_utf16(&encoded.units)?;
+
+        Ok(encoded.bytes_for_units(units_written))
+    }
+
+    /// Writes UTF-16 code units to a console screen buffer beginning at the current cursor location.
+    /// Returns the number of code units that have been written.
+    ///
+    /// This wraps
+    /// [`WriteConsoleW`](https://docs.microsoft.com/en-us/windows/console/writeconsole).
+    pub fn write_utf16(&self, units: &[u16]) -> Result<usize> {
+        let utf16_ptr: *const c_void = units.as_ptr() as *const c_void;
+
+        let mut units_written: u32 = 0;
+
+        result("WriteConsoleW", unsafe {
             WriteConsoleW(
                 *self.handle,
                 utf16_ptr,
-                utf16.len() as u32,
-                &mut cells_written,
+                units.len() as u32,
+                &mut units_written,
                 NULL,
             )
+        })
+        .map_err(|e| {
+            e.with_handle(&self.handle)
+                .with_argument("units", &units.len())
         })?;
 
-        Ok(utf8.as_bytes().len())
+        Ok(units_written as usize)
     }
 
     /// Read one input event.
@@ -203,31 +275,90 @@ impl Console {
     /// [`GetNumberOfConsoleInputEvents`](https://docs.microsoft.com/en-us/windows/console/getnumberofconsoleinputevents).
     pub fn number_of_console_input_events(&self) -> Result<u32> {
         let mut buf_len: DWORD = 0;
-        result(unsafe { GetNumberOfConsoleInputEvents(*self.handle, &mut buf_len) })?;
+        result("GetNumberOfConsoleInputEvents", unsafe {
+            GetNumberOfConsoleInputEvents(*self.handle, &mut buf_len)
+        })
+        .map_err(|e| e.with_handle(&self.handle))?;
         Ok(buf_len)
     }
 
+    /// Wait at most `timeout` for input events to become available, returning whether there
+    /// are input events to read.
+    ///
+    /// The console has to wrap an input handle, like [`Handle::current_in_handle`].
+    ///
+    /// This wraps
+    /// [`WaitForSingleObject`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-waitforsingleobject).
+    pub fn poll(&self, timeout: Duration) -> Result<bool> {
+        Ok(wait_one(&self.handle, Some(timeout))? != WaitResult::TimedOut)
+    }
+
+    /// Wait until input events become available or `semaphore` is released, for at most
+    /// `timeout` or forever if it is `None`.
+    ///
+    /// This lets another thread wake up a thread that waits for input by releasing the semaphore.
+    /// A released semaphore takes precedence over available input, and waking up on it takes
+    /// its permit. The console has to wrap an input handle, like [`Handle::current_in_handle`].
+    ///
+    /// This wraps
+    /// [`WaitForMultipleObjects`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-waitformultipleobjects).
+    pub fn poll_with_semaphore(
+        &self,
+        semaphore: &Semaphore,
+        timeout: Option<Duration>,
+    ) -> Result<Wakeup> {
+        Ok(
+            match wait_any(&[semaphore as &dyn Waitable, self], timeout)? {
+                WaitResult::TimedOut => Wakeup::TimedOut,
+                WaitResult::Signalled(0) | WaitResult::Abandoned(0) => Wakeup::Semaphore,
+                _ => Wakeup::Input,
+            },
+        )
+    }
+
     /// Read input (via ReadConsoleInputW) into buf and return the number
     /// of events read. ReadConsoleInputW guarantees that at least one event
     /// is read, even if it means blocking the thread. buf.len() must fit in
     /// a u32.
     fn read_input(&self, buf: &mut [INPUT_RECORD]) -> Result<usize> {
         let mut num_records = 0;
-        debug_assert!(buf.len() < std::u32::MAX as usize);
+        debug_assert!(buf.len() < u32::MAX as usize);
 
-        result(unsafe {
+        result("ReadConsoleInputW", unsafe {
             ReadConsoleInputW(
                 *self.handle,
                 buf.as_mut_ptr(),
                 buf.len() as u32,
                 &mut num_records,
             )
+        })
+        .map_err(|e| {
+            e.with_handle(&self.handle)
+                .with_argument("records", &buf.len())
         })?;
 
         Ok(num_records as usize)
     }
 }
 
+impl WriteUtf16 for Console {
+    fn write_utf16(&mut self, units: &[u16]) -> io::Result<usize> {
+        Ok(Console::write_utf16(self, units)?)
+    }
+}
+
+impl AsHandle for Console {
+    fn as_handle(&self) -> BorrowedHandle<'_> {
+        self.handle.as_handle()
+    }
+}
+
+impl Waitable for Console {
+    fn handle(&self) -> &Handle {
+        &self.handle
+    }
+}
+
 impl From<Handle> for Console {
     /// Create a `Console` instance who's functions will be executed on the the given `Handle`
     fn from(handle: Handle) -> Self {
@@ -1,8 +1,8 @@
-use std::io::Result;
+use std::os::windows::io::{AsHandle, BorrowedHandle};
 
 use winapi::um::consoleapi::{GetConsoleMode, SetConsoleMode};
 
-use super::{result, Handle, HandleType};
+use super::{result, Handle, HandleType, Result};
 
 /// A wrapper around a screen buffer, focusing on calls to get and set the console mode.
 ///
@@ -32,7 +32,13 @@ impl ConsoleMode {
     /// This wraps
     /// [`SetConsoleMode`](https://docs.microsoft.com/en-us/windows/console/setconsolemode).
     pub fn set_mode(&self, console_mode: u32) -> Result<()> {
-        result(unsafe { SetConsoleMode(*self.handle, console_mode) })
+        result("SetConsoleMode", unsafe {
+            SetConsoleMode(*self.handle, console_mode)
+        })
+        .map_err(|e| {
+            e.with_handle(&self.handle)
+                .with_argument("mode", &format_args!("{:#06x}", console_mode))
+        })
     }
 
     /// Get the console mode.
@@ -43,11 +49,20 @@ impl ConsoleMode {
     /// [`GetConsoleMode`](https://docs.microsoft.com/en-us/windows/console/getconsolemode).
     pub fn mode(&self) -> Result<u32> {
         let mut console_mode = 0;
-        result(unsafe { GetConsoleMode(*self.handle, &mut console_mode) })?;
+        result("GetConsoleMode", unsafe {
+            GetConsoleMode(*self.handle, &mut console_mode)
+        })
+        .map_err(|e| e.with_handle(&self.handle))?;
         Ok(console_mode)
     }
 }
 
+impl AsHandle for ConsoleMode {
+    fn as_handle(&self) -> BorrowedHandle<'_> {
+        self.handle.as_handle()
+    }
+}
+
 impl From<Handle> for ConsoleMode {
     fn from(handle: Handle) -> Self {
         ConsoleMode { handle }
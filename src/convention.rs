@@ -0,0 +1,207 @@
+//! This module contains the conventions by which WinAPI functions report failure through their
+//! return value, and the conversion of a return value into a [`Result`] following one of them.
+//!
+//! Which convention a function follows is documented with the function. On failure, the reason
+//! is available from `GetLastError`, but only then: on success the last error may be stale, so it
+//! is only read when the return value signals failure.
+//!
+//! The conventions work on plain values and the last error is passed in, so they can be tested on
+//! every platform.
+
+use crate::{Coord, Error, Result};
+
+/// A documented way in which WinAPI functions returning a `V` report failure.
+pub trait Convention<V> {
+    /// What the return value is converted to on success.
+    type Output;
+
+    /// Get the output for `value`, or `None` if it reports failure.
+    fn success(&self, value: V) -> Option<Self::Output>;
+}
+
+/// Functions returning a `BOOL` that is zero on failure, like `SetConsoleMode`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct NonZero;
+
+impl Convention<i32> for NonZero {
+    type Output = ();
+
+    fn success(&self, value: i32) -> Option<()> {
+        if value != 0 {
+            Some(())
+        } else {
+            None
+        }
+    }
+}
+
+/// Functions returning a `COORD` that is `(0, 0)` on failure, like
+/// `GetLargestConsoleWindowSize`.
+///
+/// A single zero dimension is a legitimate value.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct NonZeroCoord;
+
+impl Convention<Coord> for NonZeroCoord {
+    type Output = Coord;
+
+    fn success(&self, value: Coord) -> Option<Coord> {
+        if value != Coord::new(0, 0) {
+            Some(value)
+        } else {
+            None
+        }
+    }
+}
+
+/// Functions returning a handle that is `INVALID_HANDLE_VALUE` on failure, like `CreateFileW`
+/// and `CreateConsoleScreenBuffer`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ValidHandle;
+
+impl<T> Convention<*mut T> for ValidHandle {
+    type Output = *mut T;
+
+    fn success(&self, value: *mut T) -> Option<*mut T> {
+        // `INVALID_HANDLE_VALUE` is defined as `(HANDLE)-1`.
+        if value as isize != -1 {
+            Some(value)
+        } else {
+            None
+        }
+    }
+}
+
+/// Functions returning a handle that is `NULL` on failure, like `CreateEventW`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct NonNullHandle;
+
+impl<T> Convention<*mut T> for NonNullHandle {
+    type Output = *mut T;
+
+    fn success(&self, value: *mut T) -> Option<*mut T> {
+        if value.is_null() {
+            None
+        } else {
+            Some(value)
+        }
+    }
+}
+
+/// Convert the `value` returned by the WinAPI function `api` following `convention`.
+///
+/// If the value reports failure, the error has the Win32 error code returned by `last_error`,
+/// which is not called otherwise.
+pub fn convert<C, V>(
+    convention: C,
+    api: &'static str,
+    value: V,
+    last_error: impl FnOnce() -> u32,
+) -> Result<C::Output>
+where
+    C: Convention<V>,
+{
+    convention
+        .success(value)
+        .ok_or_else(|| Error::from_code(api, last_error()))
+}
+
+/// Convert the `value` returned by the WinAPI function `api` following `convention`, taking the
+/// error code from `GetLastError` on failure.
+#[cfg(windows)]
+pub(crate) fn check<C, V>(convention: C, api: &'static str, value: V) -> Result<C::Output>
+where
+    C: Convention<V>,
+{
+    convert(convention, api, value, last_error)
+}
+
+/// Get the error code of the last failed WinAPI call on this thread from `GetLastError`.
+#[cfg(windows)]
+pub(crate) fn last_error() -> u32 {
+    std::io::Error::last_os_error()
+        .raw_os_error()
+        .map_or(0, |code| code as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use super::{convert, NonNullHandle, NonZero, NonZeroCoord, ValidHandle};
+    use crate::{Coord, ErrorKind};
+
+    fn unreachable_error() -> u32 {
+        panic!("the last error must only be read on failure")
+    }
+
+    #[test]
+    fn test_non_zero() {
+        assert!(convert(NonZero, "SetConsoleMode", 1, unreachable_error).is_ok());
+        assert!(convert(NonZero, "SetConsoleMode", -1, unreachable_error).is_ok());
+
+        let error = convert(NonZero, "SetConsoleMode", 0, || 6).unwrap_err();
+        assert_eq!(error.api(), "SetConsoleMode");
+        assert_eq!(error.code(), Some(6));
+        assert_eq!(error.kind(), ErrorKind::NotAConsole);
+    }
+
+    #[test]
+    fn test_non_zero_coord() {
+        let api = "GetLargestConsoleWindowSize";
+        for &(x, y) in &[(0, 5), (5, 0), (120, 40)] {
+            assert_eq!(
+                convert(NonZeroCoord, api, Coord::new(x, y), unreachable_error).unwrap(),
+                Coord::new(x, y)
+            );
+        }
+
+        let error = convert(NonZeroCoord, api, Coord::new(0, 0), || 6).unwrap_err();
+        assert_eq!(error.code(), Some(6));
+    }
+
+    #[test]
+    fn test_valid_handle() {
+        let mut object = 0u8;
+        let handle: *mut u8 = &mut object;
+        assert_eq!(
+            convert(ValidHandle, "CreateFileW", handle, unreachable_error).unwrap(),
+            handle
+        );
+        // Only `INVALID_HANDLE_VALUE` reports failure, `NULL` doesn't.
+        assert!(convert(
+            ValidHandle,
+            "GetStdHandle",
+            ptr::null_mut::<u8>(),
+            unreachable_error
+        )
+        .unwrap()
+        .is_null());
+
+        let error = convert(ValidHandle, "CreateFileW", -1isize as *mut u8, || 5).unwrap_err();
+        assert_eq!(error.code(), Some(5));
+        assert_eq!(error.kind(), ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn test_non_null_handle() {
+        let mut object = 0u8;
+        let handle: *mut u8 = &mut object;
+        assert_eq!(
+            convert(NonNullHandle, "CreateEventW", handle, unreachable_error).unwrap(),
+            handle
+        );
+        // Only `NULL` reports failure, `INVALID_HANDLE_VALUE` doesn't.
+        assert!(convert(
+            NonNullHandle,
+            "OpenEventW",
+            -1isize as *mut u8,
+            unreachable_error
+        )
+        .is_ok());
+
+        let error = convert(NonNullHandle, "OpenEventW", ptr::null_mut::<u8>(), || 2).unwrap_err();
+        assert_eq!(error.code(), Some(2));
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+    }
+}
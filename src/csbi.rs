@@ -1,82 +1,127 @@
-use std::fmt;
-use std::mem::zeroed;
-
+#[cfg(windows)]
 use winapi::um::wincon::CONSOLE_SCREEN_BUFFER_INFO;
 
-use super::{Coord, Size, WindowPositions};
+use super::{Attributes, Coord, ScreenBufferInfoEx, Size, WindowPositions};
 
 /// Information about a console screen buffer.
 ///
-/// This wraps
+/// This is built from
 /// [`CONSOLE_SCREEN_BUFFER_INFO`](https://docs.microsoft.com/en-us/windows/console/console-screen-buffer-info-str).
-// TODO: replace the innards of this type with our own, more friendly types, like Coord.
-// This will obviously be a breaking change.
-#[derive(Clone)]
-pub struct ScreenBufferInfo(pub CONSOLE_SCREEN_BUFFER_INFO);
-
-// TODO: replace this with a derive ASAP
-impl fmt::Debug for ScreenBufferInfo {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_struct("ScreenBufferInfo")
-            .field("dwSize", &self.buffer_size())
-            .field("dwCursorPosition", &self.cursor_pos())
-            .field("wAttributes", &self.attributes()) // TODO: hex print this
-            .field("srWindow", &self.terminal_window())
-            .field(
-                "dwMaximumWindowSize",
-                &Size::from(self.0.dwMaximumWindowSize),
-            )
-            .finish()
-    }
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ScreenBufferInfo {
+    /// The size of the screen buffer, in character columns and rows.
+    pub buffer_size: Size,
+    /// The column and row of the cursor in the screen buffer.
+    pub cursor_position: Coord,
+    /// The attributes of the characters written to the screen buffer.
+    pub attributes: Attributes,
+    /// The screen buffer coordinates of the upper-left and lower-right corners of the window.
+    pub window: WindowPositions,
+    /// The maximum size of the console window, given the current screen buffer size and font
+    /// and the screen size.
+    pub maximum_window_size: Size,
 }
 
 impl ScreenBufferInfo {
-    /// Create a new console screen buffer without all zeroed properties.
-    pub fn new() -> ScreenBufferInfo {
-        ScreenBufferInfo(unsafe { zeroed() })
-    }
-
     /// Get the size of the screen buffer.
-    ///
-    /// Will take `dwSize` from the current screen buffer and convert it into a [`Size`].
     pub fn buffer_size(&self) -> Size {
-        Size::from(self.0.dwSize)
+        self.buffer_size
     }
 
     /// Get the size of the terminal display window.
     ///
-    /// Will calculate the width and height from `srWindow` and convert it into a [`Size`].
+    /// The corners of the window are inclusive, so a window from column 0 to column 79 is 80
+    /// columns wide.
     pub fn terminal_size(&self) -> Size {
-        Size::new(
-            self.0.srWindow.Right - self.0.srWindow.Left,
-            self.0.srWindow.Bottom - self.0.srWindow.Top,
-        )
+        self.window.size()
     }
 
     /// Get the position and size of the terminal display window.
-    ///
-    /// Will take `srWindow` and convert it into the `WindowPositions` type.
     pub fn terminal_window(&self) -> WindowPositions {
-        WindowPositions::from(self.0)
+        self.window
     }
 
     /// Get the current attributes of the characters that are being written to the console.
-    ///
-    /// Will take `wAttributes` from the current screen buffer.
-    pub fn attributes(&self) -> u16 {
-        self.0.wAttributes
+    pub fn attributes(&self) -> Attributes {
+        self.attributes
     }
 
     /// Get the current column and row of the terminal cursor in the screen buffer.
-    ///
-    /// Will take `dwCursorPosition` from the current screen buffer.
     pub fn cursor_pos(&self) -> Coord {
-        Coord::from(self.0.dwCursorPosition)
+        self.cursor_position
     }
 }
 
+#[cfg(windows)]
 impl From<CONSOLE_SCREEN_BUFFER_INFO> for ScreenBufferInfo {
     fn from(csbi: CONSOLE_SCREEN_BUFFER_INFO) -> Self {
-        ScreenBufferInfo(csbi)
+        ScreenBufferInfo {
+            buffer_size: Size::from(csbi.dwSize),
+            cursor_position: Coord::from(csbi.dwCursorPosition),
+            attributes: Attributes::from(csbi.wAttributes),
+            window: WindowPositions::from(csbi.srWindow),
+            maximum_window_size: Size::from(csbi.dwMaximumWindowSize),
+        }
+    }
+}
+
+impl From<ScreenBufferInfoEx> for ScreenBufferInfo {
+    fn from(info: ScreenBufferInfoEx) -> Self {
+        ScreenBufferInfo {
+            buffer_size: info.buffer_size,
+            cursor_position: info.cursor_position,
+            attributes: info.attributes,
+            window: info.window,
+            maximum_window_size: info.maximum_window_size,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ScreenBufferInfo;
+    use crate::{Size, WindowPositions};
+
+    #[test]
+    fn test_terminal_size_is_inclusive() {
+        let info = ScreenBufferInfo {
+            buffer_size: Size::new(80, 9001),
+            window: WindowPositions {
+                left: 0,
+                right: 79,
+                bottom: 124,
+                top: 100,
+            },
+            ..ScreenBufferInfo::default()
+        };
+
+        assert_eq!(info.terminal_size(), Size::new(80, 25));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_from_console_screen_buffer_info() {
+        use winapi::um::wincon::{CONSOLE_SCREEN_BUFFER_INFO, COORD, SMALL_RECT};
+
+        use crate::{Attributes, Coord};
+
+        let info = ScreenBufferInfo::from(CONSOLE_SCREEN_BUFFER_INFO {
+            dwSize: COORD { X: 120, Y: 30 },
+            dwCursorPosition: COORD { X: 5, Y: 6 },
+            wAttributes: 0x0007,
+            srWindow: SMALL_RECT {
+                Left: 0,
+                Top: 0,
+                Right: 119,
+                Bottom: 29,
+            },
+            dwMaximumWindowSize: COORD { X: 120, Y: 60 },
+        });
+
+        assert_eq!(info.buffer_size, Size::new(120, 30));
+        assert_eq!(info.cursor_pos(), Coord::new(5, 6));
+        assert_eq!(info.attributes(), Attributes::from(0x0007));
+        assert_eq!(info.terminal_size(), Size::new(120, 30));
+        assert_eq!(info.maximum_window_size, Size::new(120, 60));
     }
 }
@@ -0,0 +1,118 @@
+#[cfg(windows)]
+use std::mem::{size_of, zeroed};
+
+#[cfg(windows)]
+use winapi::um::wincon::CONSOLE_SCREEN_BUFFER_INFOEX;
+
+use super::{Attributes, ColorTable, Coord, Size, WindowPositions};
+
+/// Extended information about a console screen buffer, including its color table.
+///
+/// This is built from
+/// [`CONSOLE_SCREEN_BUFFER_INFOEX`](https://docs.microsoft.com/en-us/windows/console/console-screen-buffer-infoex)
+/// and can be read with [`ScreenBuffer::info_ex`](crate::ScreenBuffer::info_ex) and written back
+/// with [`ScreenBuffer::set_info_ex`](crate::ScreenBuffer::set_info_ex).
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ScreenBufferInfoEx {
+    /// The size of the screen buffer, in character columns and rows.
+    pub buffer_size: Size,
+    /// The column and row of the cursor in the screen buffer.
+    pub cursor_position: Coord,
+    /// The attributes of the characters written to the screen buffer.
+    pub attributes: Attributes,
+    /// The screen buffer coordinates of the upper-left and lower-right corners of the window.
+    pub window: WindowPositions,
+    /// The maximum size of the console window, given the current screen buffer size and font
+    /// and the screen size.
+    pub maximum_window_size: Size,
+    /// The attributes of the text in popups, like the command history popup of `cmd.exe`.
+    pub popup_attributes: Attributes,
+    /// Whether the console supports full-screen mode.
+    pub fullscreen_supported: bool,
+    /// The colors the console uses to display the color attributes.
+    pub color_table: ColorTable,
+}
+
+#[cfg(windows)]
+impl ScreenBufferInfoEx {
+    /// Create a new, zeroed `CONSOLE_SCREEN_BUFFER_INFOEX` with its `cbSize` member set, as
+    /// required by `GetConsoleScreenBufferInfoEx`.
+    pub(crate) fn raw() -> CONSOLE_SCREEN_BUFFER_INFOEX {
+        let mut csbi: CONSOLE_SCREEN_BUFFER_INFOEX = unsafe { zeroed() };
+        csbi.cbSize = size_of::<CONSOLE_SCREEN_BUFFER_INFOEX>() as u32;
+        csbi
+    }
+}
+
+#[cfg(windows)]
+impl From<CONSOLE_SCREEN_BUFFER_INFOEX> for ScreenBufferInfoEx {
+    fn from(csbi: CONSOLE_SCREEN_BUFFER_INFOEX) -> Self {
+        ScreenBufferInfoEx {
+            buffer_size: Size::from(csbi.dwSize),
+            cursor_position: Coord::from(csbi.dwCursorPosition),
+            attributes: Attributes::from(csbi.wAttributes),
+            window: WindowPositions::from(csbi.srWindow),
+            maximum_window_size: Size::from(csbi.dwMaximumWindowSize),
+            popup_attributes: Attributes::from(csbi.wPopupAttributes),
+            fullscreen_supported: csbi.bFullscreenSupported != 0,
+            color_table: ColorTable::from_colorrefs(csbi.ColorTable),
+        }
+    }
+}
+
+#[cfg(windows)]
+impl From<ScreenBufferInfoEx> for CONSOLE_SCREEN_BUFFER_INFOEX {
+    fn from(info: ScreenBufferInfoEx) -> Self {
+        let mut csbi = ScreenBufferInfoEx::raw();
+        csbi.dwSize = info.buffer_size.into();
+        csbi.dwCursorPosition = info.cursor_position.into();
+        csbi.wAttributes = info.attributes.bits();
+        csbi.srWindow = info.window.into();
+        csbi.dwMaximumWindowSize = info.maximum_window_size.into();
+        csbi.wPopupAttributes = info.popup_attributes.bits();
+        csbi.bFullscreenSupported = info.fullscreen_supported as i32;
+        csbi.ColorTable = info.color_table.to_colorrefs();
+        csbi
+    }
+}
+
+#[cfg(all(test, windows))]
+mod tests {
+    use std::mem::size_of;
+
+    use winapi::um::wincon::CONSOLE_SCREEN_BUFFER_INFOEX;
+
+    use super::ScreenBufferInfoEx;
+    use crate::{Attributes, ColorTable, Coord, Rgb, Size, WindowPositions};
+
+    #[test]
+    fn test_screen_buffer_info_ex_roundtrip() {
+        let mut color_table = ColorTable::default();
+        color_table[0] = Rgb::new(12, 12, 12);
+        color_table[15] = Rgb::new(242, 242, 242);
+
+        let info = ScreenBufferInfoEx {
+            buffer_size: Size::new(120, 9001),
+            cursor_position: Coord::new(3, 40),
+            attributes: Attributes::from(0x0007),
+            window: WindowPositions {
+                left: 0,
+                right: 119,
+                bottom: 59,
+                top: 30,
+            },
+            maximum_window_size: Size::new(120, 70),
+            popup_attributes: Attributes::from(0x00F5),
+            fullscreen_supported: true,
+            color_table,
+        };
+
+        let raw = CONSOLE_SCREEN_BUFFER_INFOEX::from(info);
+        assert_eq!(
+            raw.cbSize as usize,
+            size_of::<CONSOLE_SCREEN_BUFFER_INFOEX>()
+        );
+        assert_eq!(raw.ColorTable[15], 0x00F2_F2F2);
+        assert_eq!(ScreenBufferInfoEx::from(raw), info);
+    }
+}
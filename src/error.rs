@@ -0,0 +1,471 @@
+//! This module contains the error type of this crate, which tells which WinAPI function failed,
+//! with which Win32 error code, on which handle and with which arguments.
+//!
+//! Classifying Win32 error codes and formatting errors doesn't call into WinAPI, so it can be
+//! tested on every platform.
+
+use std::error;
+use std::fmt;
+use std::io;
+
+use crate::win32::{
+    ERROR_ACCESS_DENIED, ERROR_ALREADY_EXISTS, ERROR_BROKEN_PIPE, ERROR_FILE_NOT_FOUND,
+    ERROR_INVALID_HANDLE, ERROR_INVALID_PARAMETER, ERROR_NOT_ENOUGH_MEMORY, ERROR_OUTOFMEMORY,
+};
+#[cfg(windows)]
+use crate::Handle;
+use crate::HandleType;
+
+/// The WinAPI functions that take a console input or screen buffer handle, for which
+/// `ERROR_INVALID_HANDLE` means the handle is not a console.
+const CONSOLE_BUFFER_APIS: &[&str] = &[
+    "FillConsoleOutputAttribute",
+    "FillConsoleOutputCharacterW",
+    "FlushConsoleInputBuffer",
+    "GetConsoleCursorInfo",
+    "GetConsoleFontSize",
+    "GetConsoleMode",
+    "GetConsoleScreenBufferInfo",
+    "GetConsoleScreenBufferInfoEx",
+    "GetCurrentConsoleFont",
+    "GetCurrentConsoleFontEx",
+    "GetLargestConsoleWindowSize",
+    "GetNumberOfConsoleInputEvents",
+    "PeekConsoleInputW",
+    "ReadConsoleInputW",
+    "ReadConsoleOutputW",
+    "ReadConsoleW",
+    "ScrollConsoleScreenBufferW",
+    "SetConsoleActiveScreenBuffer",
+    "SetConsoleCursorInfo",
+    "SetConsoleCursorPosition",
+    "SetConsoleMode",
+    "SetConsoleScreenBufferInfoEx",
+    "SetConsoleScreenBufferSize",
+    "SetConsoleTextAttribute",
+    "SetConsoleWindowInfo",
+    "SetCurrentConsoleFontEx",
+    "WriteConsoleInputW",
+    "WriteConsoleOutputW",
+    "WriteConsoleW",
+];
+
+/// A specialized [`Result`](std::result::Result) for the functions of this crate.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A classification of an [`Error`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A console function was called on a handle that is not a console, for example because
+    /// the standard output is redirected to a file.
+    NotAConsole,
+    /// The handle is invalid, for example because it was closed.
+    InvalidHandle,
+    /// Access to the object was denied.
+    PermissionDenied,
+    /// WinAPI rejected one of the arguments.
+    InvalidParameter,
+    /// A named object doesn't exist.
+    NotFound,
+    /// A named object exists already.
+    AlreadyExists,
+    /// There is not enough memory to complete the call.
+    OutOfMemory,
+    /// The other end of a pipe was closed.
+    BrokenPipe,
+    /// An argument was rejected before calling WinAPI.
+    InvalidInput,
+    /// Data passed to the function is not valid, like bytes that are not UTF-8.
+    InvalidData,
+    /// Any other error.
+    Other,
+}
+
+impl ErrorKind {
+    /// Classify the Win32 error `code` returned by a call to the WinAPI function `api`.
+    ///
+    /// `ERROR_INVALID_HANDLE` from a function that takes a console buffer handle, like
+    /// `SetConsoleWindowInfo`, means the handle is not a console, while from other functions,
+    /// including console functions like `AttachConsole` that take no such handle, it means the
+    /// handle is invalid.
+    pub fn from_code(api: &str, code: u32) -> ErrorKind {
+        match code {
+            ERROR_INVALID_HANDLE if CONSOLE_BUFFER_APIS.contains(&api) => ErrorKind::NotAConsole,
+            ERROR_INVALID_HANDLE => ErrorKind::InvalidHandle,
+            ERROR_ACCESS_DENIED => ErrorKind::PermissionDenied,
+            ERROR_INVALID_PARAMETER => ErrorKind::InvalidParameter,
+            ERROR_FILE_NOT_FOUND => ErrorKind::NotFound,
+            ERROR_ALREADY_EXISTS => ErrorKind::AlreadyExists,
+            ERROR_NOT_ENOUGH_MEMORY | ERROR_OUTOFMEMORY => ErrorKind::OutOfMemory,
+            ERROR_BROKEN_PIPE => ErrorKind::BrokenPipe,
+            _ => ErrorKind::Other,
+        }
+    }
+
+    fn from_io(kind: io::ErrorKind) -> ErrorKind {
+        match kind {
+            io::ErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+            io::ErrorKind::NotFound => ErrorKind::NotFound,
+            io::ErrorKind::AlreadyExists => ErrorKind::AlreadyExists,
+            io::ErrorKind::BrokenPipe => ErrorKind::BrokenPipe,
+            io::ErrorKind::InvalidInput => ErrorKind::InvalidInput,
+            io::ErrorKind::InvalidData => ErrorKind::InvalidData,
+            _ => ErrorKind::Other,
+        }
+    }
+
+    /// Get a short description of this kind of error.
+    pub fn description(self) -> &'static str {
+        match self {
+            ErrorKind::NotAConsole => "the handle is not a console",
+            ErrorKind::InvalidHandle => "the handle is invalid",
+            ErrorKind::PermissionDenied => "access is denied",
+            ErrorKind::InvalidParameter => "a parameter is incorrect",
+            ErrorKind::NotFound => "the object was not found",
+            ErrorKind::AlreadyExists => "the object already exists",
+            ErrorKind::OutOfMemory => "not enough memory",
+            ErrorKind::BrokenPipe => "the pipe has been ended",
+            ErrorKind::InvalidInput => "invalid input",
+            ErrorKind::InvalidData => "invalid data",
+            ErrorKind::Other => "unknown error",
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.description())
+    }
+}
+
+impl From<ErrorKind> for io::ErrorKind {
+    fn from(kind: ErrorKind) -> Self {
+        match kind {
+            ErrorKind::NotAConsole => io::ErrorKind::Unsupported,
+            ErrorKind::InvalidHandle | ErrorKind::InvalidParameter | ErrorKind::InvalidInput => {
+                io::ErrorKind::InvalidInput
+            }
+            ErrorKind::PermissionDenied => io::ErrorKind::PermissionDenied,
+            ErrorKind::NotFound => io::ErrorKind::NotFound,
+            ErrorKind::AlreadyExists => io::ErrorKind::AlreadyExists,
+            ErrorKind::OutOfMemory => io::ErrorKind::OutOfMemory,
+            ErrorKind::BrokenPipe => io::ErrorKind::BrokenPipe,
+            ErrorKind::InvalidData => io::ErrorKind::InvalidData,
+            ErrorKind::Other => io::ErrorKind::Other,
+        }
+    }
+}
+
+/// An error of a call to WinAPI, or of checking the arguments for one.
+///
+/// Besides its [kind](ErrorKind), the error records the name of the WinAPI function, the Win32
+/// error code if the function failed, the kind of handle it was called on and the arguments it
+/// was called with, which all show up when the error is displayed.
+///
+/// An error converts into an [`io::Error`], so functions returning an [`io::Result`] can use `?`
+/// on the results of this crate. An error with a Win32 error code becomes that OS error, like
+/// [`io::Error::last_os_error`], so [`io::Error::raw_os_error`] keeps working; use
+/// [`Error::into_io_with_context`] to keep the function, handle and arguments as well.
+#[derive(Debug)]
+pub struct Error {
+    api: &'static str,
+    kind: ErrorKind,
+    code: Option<u32>,
+    handle_type: Option<HandleType>,
+    arguments: Vec<(&'static str, String)>,
+    source: Option<Box<dyn error::Error + Send + Sync>>,
+}
+
+impl Error {
+    /// Create an error for a call to `api` that failed with the Win32 error `code`.
+    pub fn from_code(api: &'static str, code: u32) -> Error {
+        Error {
+            api,
+            kind: ErrorKind::from_code(api, code),
+            code: Some(code),
+            handle_type: None,
+            arguments: Vec::new(),
+            source: None,
+        }
+    }
+
+    /// Create an error for a call to `api` that didn't fail with a Win32 error code, for example
+    /// because an argument was rejected before calling it.
+    pub fn new<E>(api: &'static str, kind: ErrorKind, error: E) -> Error
+    where
+        E: Into<Box<dyn error::Error + Send + Sync>>,
+    {
+        Error {
+            api,
+            kind,
+            code: None,
+            handle_type: None,
+            arguments: Vec::new(),
+            source: Some(error.into()),
+        }
+    }
+
+    /// Create an error of kind [`InvalidInput`](ErrorKind::InvalidInput) for a `function` of this
+    /// crate, like `"ScreenBuffer::resize"`, that rejected its arguments before calling WinAPI at
+    /// all.
+    ///
+    /// [`api`](Error::api) is then the name of that function rather than of a WinAPI function.
+    pub fn validation<E>(function: &'static str, error: E) -> Error
+    where
+        E: Into<Box<dyn error::Error + Send + Sync>>,
+    {
+        Error::new(function, ErrorKind::InvalidInput, error)
+    }
+
+    /// Create an error for a call to `api` from an [`io::Error`].
+    ///
+    /// The raw OS error code of the error is taken as the Win32 error code. Other errors keep
+    /// their [`io::ErrorKind`] as far as [`ErrorKind`] has a match for it.
+    pub fn from_io(api: &'static str, error: io::Error) -> Error {
+        match error.raw_os_error() {
+            Some(code) => Error::from_code(api, code as u32),
+            None => Error::new(api, ErrorKind::from_io(error.kind()), error),
+        }
+    }
+
+    /// Create an error for a call to `api` from the last Win32 error code of the current thread.
+    ///
+    /// This wraps
+    /// [`GetLastError`](https://docs.microsoft.com/en-us/windows/win32/api/errhandlingapi/nf-errhandlingapi-getlasterror).
+    #[cfg(windows)]
+    pub fn last_os_error(api: &'static str) -> Error {
+        Error::from_io(api, io::Error::last_os_error())
+    }
+
+    /// Record the kind of handle the function was called on.
+    pub fn with_handle_type(mut self, handle_type: HandleType) -> Error {
+        self.handle_type = Some(handle_type);
+        self
+    }
+
+    /// Record the kind of `handle`, if it is known.
+    #[cfg(windows)]
+    pub(crate) fn with_handle(mut self, handle: &Handle) -> Error {
+        self.handle_type = handle.handle_type();
+        self
+    }
+
+    /// Record an argument the function was called with.
+    pub fn with_argument(mut self, name: &'static str, value: &dyn fmt::Debug) -> Error {
+        self.arguments.push((name, format!("{:?}", value)));
+        self
+    }
+
+    /// Convert into an [`io::Error`] of a matching [`io::ErrorKind`] that wraps this error, so the
+    /// function, handle and arguments show up when it is displayed. The error can be recovered
+    /// from it with [`io::Error::get_ref`] and `downcast_ref`.
+    ///
+    /// Unlike the `From` conversion, [`io::Error::raw_os_error`] of the result is `None`.
+    pub fn into_io_with_context(self) -> io::Error {
+        io::Error::new(self.kind.into(), self)
+    }
+
+    /// Get the name of the WinAPI function that failed, like `"SetConsoleWindowInfo"`, or of the
+    /// function of this crate for errors created with [`Error::validation`].
+    pub fn api(&self) -> &'static str {
+        self.api
+    }
+
+    /// Get the kind of this error.
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// Get the Win32 error code the function failed with, if it was called.
+    pub fn code(&self) -> Option<u32> {
+        self.code
+    }
+
+    /// Get the kind of handle the function was called on, if it is known.
+    pub fn handle_type(&self) -> Option<HandleType> {
+        self.handle_type
+    }
+
+    /// Get the names of the arguments the function was called with, and their values as they
+    /// are formatted with `Debug`.
+    pub fn arguments(&self) -> &[(&'static str, String)] {
+        &self.arguments
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} failed", self.api)?;
+        if let Some(handle_type) = self.handle_type {
+            write!(f, " on the {}", handle_type)?;
+        }
+        if !self.arguments.is_empty() {
+            f.write_str(" (")?;
+            for (i, (name, value)) in self.arguments.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{}: {}", name, value)?;
+            }
+            f.write_str(")")?;
+        }
+
+        match &self.source {
+            Some(source) => write!(f, ": {}", source),
+            None => write!(f, ": {}", self.kind),
+        }?;
+        match self.code {
+            Some(code) => write!(f, " (os error {})", code),
+            None => Ok(()),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match &self.source {
+            Some(source) => Some(&**source),
+            None => None,
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    /// Convert an error with a Win32 error code into that OS error, and wrap other errors like
+    /// [`Error::into_io_with_context`].
+    fn from(error: Error) -> Self {
+        match error.code {
+            Some(code) => io::Error::from_raw_os_error(code as i32),
+            None => error.into_io_with_context(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::{Error, ErrorKind};
+    use crate::HandleType;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            ErrorKind::from_code("SetConsoleWindowInfo", 6),
+            ErrorKind::NotAConsole
+        );
+        assert_eq!(
+            ErrorKind::from_code("WaitForSingleObject", 6),
+            ErrorKind::InvalidHandle
+        );
+        // Console functions that don't take a console buffer handle report invalid handles.
+        for &api in &[
+            "AttachConsole",
+            "FreeConsole",
+            "CreatePseudoConsole",
+            "ResizePseudoConsole",
+            "GetConsoleTitleW",
+        ] {
+            assert_eq!(ErrorKind::from_code(api, 6), ErrorKind::InvalidHandle);
+        }
+        assert_eq!(
+            ErrorKind::from_code("OpenSemaphoreW", 2),
+            ErrorKind::NotFound
+        );
+        assert_eq!(
+            ErrorKind::from_code("CreateFileW", 5),
+            ErrorKind::PermissionDenied
+        );
+        assert_eq!(
+            ErrorKind::from_code("SetConsoleScreenBufferSize", 87),
+            ErrorKind::InvalidParameter
+        );
+        assert_eq!(
+            ErrorKind::from_code("WriteConsoleW", 1234),
+            ErrorKind::Other
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let error = Error::from_code("SetConsoleWindowInfo", 6)
+            .with_handle_type(HandleType::OutputHandle)
+            .with_argument("absolute", &true)
+            .with_argument("rect", &(0, 0, 79, 24));
+        assert_eq!(
+            error.to_string(),
+            "SetConsoleWindowInfo failed on the standard output handle \
+             (absolute: true, rect: (0, 0, 79, 24)): the handle is not a console (os error 6)"
+        );
+
+        let error = Error::new(
+            "FillConsoleOutputCharacterW",
+            ErrorKind::InvalidInput,
+            "character U+1F600 can not fill a console cell",
+        );
+        assert_eq!(
+            error.to_string(),
+            "FillConsoleOutputCharacterW failed: character U+1F600 can not fill a console cell"
+        );
+
+        let error = Error::validation(
+            "ScreenBuffer::resize",
+            "console size 0x25 is smaller than 1x1",
+        )
+        .with_argument("width", &0);
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        assert_eq!(error.code(), None);
+        assert_eq!(
+            error.to_string(),
+            "ScreenBuffer::resize failed (width: 0): console size 0x25 is smaller than 1x1"
+        );
+    }
+
+    #[test]
+    fn test_from_io() {
+        let error = Error::from_io("GetConsoleMode", io::Error::from_raw_os_error(6));
+        assert_eq!(error.code(), Some(6));
+        assert_eq!(error.kind(), ErrorKind::NotAConsole);
+
+        let error = Error::from_io(
+            "CreateSemaphoreW",
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "string contains a NUL character",
+            ),
+        );
+        assert_eq!(error.code(), None);
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        assert_eq!(
+            error.to_string(),
+            "CreateSemaphoreW failed: string contains a NUL character"
+        );
+    }
+
+    #[test]
+    fn test_into_io_error() {
+        // The Win32 error code survives the conversion, like for `io::Error::last_os_error`.
+        let error = io::Error::from(Error::from_code("GetConsoleMode", 6));
+        assert_eq!(error.raw_os_error(), Some(6));
+
+        let error = io::Error::from(Error::new(
+            "WriteConsoleW",
+            ErrorKind::InvalidData,
+            "stream did not contain valid UTF-8",
+        ));
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.get_ref().unwrap().downcast_ref::<Error>().is_some());
+
+        let error = Error::from_code("GetConsoleMode", 6)
+            .with_handle_type(HandleType::InputHandle)
+            .into_io_with_context();
+        assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+        assert_eq!(error.raw_os_error(), None);
+
+        let inner = error.get_ref().unwrap().downcast_ref::<Error>().unwrap();
+        assert_eq!(inner.api(), "GetConsoleMode");
+        assert_eq!(inner.code(), Some(6));
+        assert_eq!(inner.handle_type(), Some(HandleType::InputHandle));
+    }
+}
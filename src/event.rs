@@ -0,0 +1,200 @@
+use std::os::windows::io::{AsHandle, BorrowedHandle};
+use std::ptr;
+use std::time::Duration;
+
+use winapi::shared::minwindef::{BOOL, FALSE};
+use winapi::um::synchapi::{CreateEventW, OpenEventW, ResetEvent, SetEvent};
+use winapi::um::winnt::{EVENT_MODIFY_STATE, SYNCHRONIZE};
+
+use crate::wait::wait_one;
+use crate::{nonnull_handle_result, result, wide_nul, Error, Handle, Result, WaitResult, Waitable};
+
+/// A [Windows event object](https://docs.microsoft.com/en-us/windows/win32/sync/event-objects).
+///
+/// An event is either signalled or not. A manual-reset event stays signalled until it is
+/// [`reset`](Event::reset), so it wakes up every thread waiting for it. An auto-reset event is
+/// reset by the first wait that finds it signalled, so it wakes up a single thread.
+///
+/// Clones refer to the same event.
+#[derive(Clone, Debug)]
+pub struct Event(Handle);
+
+impl Event {
+    /// Construct a new auto-reset event that is not signalled.
+    ///
+    /// This wraps
+    /// [`CreateEventW`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-createeventw).
+    pub fn auto_reset() -> Result<Event> {
+        Event::builder().build()
+    }
+
+    /// Construct a new manual-reset event that is not signalled.
+    ///
+    /// This wraps
+    /// [`CreateEventW`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-createeventw).
+    pub fn manual_reset() -> Result<Event> {
+        Event::builder().manual_reset(true).build()
+    }
+
+    /// Start building an event that is signalled from the start or has a name.
+    pub fn builder() -> EventBuilder {
+        EventBuilder::new()
+    }
+
+    /// Open an existing named event.
+    ///
+    /// This wraps
+    /// [`OpenEventW`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-openeventw).
+    pub fn open(name: &str) -> Result<Event> {
+        let error = |e: Error| e.with_argument("name", &name);
+        let wide_name = wide_nul("OpenEventW", name).map_err(error)?;
+        let handle = nonnull_handle_result("OpenEventW", unsafe {
+            OpenEventW(SYNCHRONIZE | EVENT_MODIFY_STATE, FALSE, wide_name.as_ptr())
+        })
+        .map_err(error)?;
+
+        let handle = unsafe { Handle::from_raw(handle) };
+        Ok(Event(handle))
+    }
+
+    /// Signal the event.
+    ///
+    /// This wraps
+    /// [`SetEvent`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-setevent).
+    pub fn set(&self) -> Result<()> {
+        result("SetEvent", unsafe { SetEvent(*self.0) }).map_err(|e| e.with_handle(&self.0))
+    }
+
+    /// Make the event not signalled.
+    ///
+    /// This wraps
+    /// [`ResetEvent`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-resetevent).
+    pub fn reset(&self) -> Result<()> {
+        result("ResetEvent", unsafe { ResetEvent(*self.0) }).map_err(|e| e.with_handle(&self.0))
+    }
+
+    /// Wait until the event is signalled.
+    pub fn wait(&self) -> Result<()> {
+        wait_one(&self.0, None)?;
+        Ok(())
+    }
+
+    /// Wait at most `timeout` until the event is signalled, returning whether it was.
+    pub fn wait_timeout(&self, timeout: Duration) -> Result<bool> {
+        Ok(wait_one(&self.0, Some(timeout))? != WaitResult::TimedOut)
+    }
+
+    /// Access the underlying handle to the event.
+    pub fn handle(&self) -> &Handle {
+        &self.0
+    }
+}
+
+impl AsHandle for Event {
+    fn as_handle(&self) -> BorrowedHandle<'_> {
+        self.0.as_handle()
+    }
+}
+
+impl Waitable for Event {
+    fn handle(&self) -> &Handle {
+        &self.0
+    }
+}
+
+/// A builder for an [`Event`].
+#[derive(Clone, Debug, Default)]
+pub struct EventBuilder {
+    manual_reset: bool,
+    signalled: bool,
+    name: Option<String>,
+}
+
+impl EventBuilder {
+    /// Create a builder for an auto-reset event that is not signalled.
+    pub fn new() -> EventBuilder {
+        EventBuilder::default()
+    }
+
+    /// Set whether the event is manual-reset instead of auto-reset.
+    pub fn manual_reset(mut self, manual_reset: bool) -> EventBuilder {
+        self.manual_reset = manual_reset;
+        self
+    }
+
+    /// Set whether the event starts out signalled.
+    pub fn signalled(mut self, signalled: bool) -> EventBuilder {
+        self.signalled = signalled;
+        self
+    }
+
+    /// Set the name of the event, so other processes can open it with [`Event::open`].
+    ///
+    /// If an event with this name already exists, building opens that event and the other
+    /// settings of the builder are ignored.
+    pub fn name(mut self, name: &str) -> EventBuilder {
+        self.name = Some(name.to_owned());
+        self
+    }
+
+    /// Create the event.
+    ///
+    /// This wraps
+    /// [`CreateEventW`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-createeventw).
+    pub fn build(&self) -> Result<Event> {
+        let error = |e: Error| {
+            e.with_argument("manual_reset", &self.manual_reset)
+                .with_argument("signalled", &self.signalled)
+                .with_argument("name", &self.name)
+        };
+        let name = self
+            .name
+            .as_deref()
+            .map(|name| wide_nul("CreateEventW", name))
+            .transpose()
+            .map_err(error)?;
+        let handle = nonnull_handle_result("CreateEventW", unsafe {
+            CreateEventW(
+                ptr::null_mut(),
+                BOOL::from(self.manual_reset),
+                BOOL::from(self.signalled),
+                name.as_ref().map_or(ptr::null(), |name| name.as_ptr()),
+            )
+        })
+        .map_err(error)?;
+
+        let handle = unsafe { Handle::from_raw(handle) };
+        Ok(Event(handle))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Event;
+
+    #[test]
+    fn test_auto_reset() {
+        let event = Event::auto_reset().unwrap();
+        assert!(!event.wait_timeout(Duration::from_millis(1)).unwrap());
+
+        event.set().unwrap();
+        assert!(event.wait_timeout(Duration::from_millis(1)).unwrap());
+        assert!(!event.wait_timeout(Duration::from_millis(1)).unwrap());
+    }
+
+    #[test]
+    fn test_manual_reset() {
+        let event = Event::builder()
+            .manual_reset(true)
+            .signalled(true)
+            .build()
+            .unwrap();
+        event.wait().unwrap();
+        event.wait().unwrap();
+
+        event.reset().unwrap();
+        assert!(!event.wait_timeout(Duration::from_millis(1)).unwrap());
+    }
+}
@@ -0,0 +1,402 @@
+//! This module contains an asynchronous stream of input events.
+//!
+//! Reading from the console blocks, so the events are read on a separate thread that waits for
+//! either new events or a cancellation, and puts them in a queue that the [`EventStream`] takes
+//! them from. With the `tokio` feature, that thread comes from the blocking pool of the current
+//! Tokio runtime, with the `async-std` feature from the blocking pool of async-std, and otherwise
+//! it is a thread of its own.
+
+use std::collections::VecDeque;
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll, Waker};
+#[cfg(not(feature = "async-std"))]
+use std::thread;
+
+use futures_core::Stream;
+
+#[cfg(windows)]
+use super::{Console, Handle, InputRecord, Semaphore, Wakeup};
+
+/// A way to wake up a thread that is waiting in [`EventSource::wait`].
+pub trait Cancel {
+    /// Make the current or next call to [`EventSource::wait`] return `false`.
+    fn cancel(&self) -> io::Result<()>;
+}
+
+#[cfg(windows)]
+impl Cancel for Semaphore {
+    fn cancel(&self) -> io::Result<()> {
+        self.release(1)?;
+        Ok(())
+    }
+}
+
+/// A source of events that a thread can wait for, like the input buffer of the console.
+pub trait EventSource: Send + 'static {
+    /// The type of the events.
+    type Event: Send + 'static;
+    /// The type used to cancel a wait from another thread.
+    type Canceller: Cancel + Send + Sync + 'static;
+
+    /// Block until events are available, returning `true`, or until the wait is cancelled,
+    /// returning `false`.
+    fn wait(&mut self) -> io::Result<bool>;
+
+    /// Read the events that are available without blocking.
+    fn read(&mut self) -> io::Result<Vec<Self::Event>>;
+
+    /// Get the object used to cancel a wait.
+    fn canceller(&self) -> Self::Canceller;
+}
+
+/// The state shared by an [`EventStream`] and the thread reading its events.
+#[derive(Debug)]
+struct Shared<T> {
+    queue: VecDeque<io::Result<T>>,
+    waker: Option<Waker>,
+    finished: bool,
+}
+
+impl<T> Shared<T> {
+    fn wake(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+fn lock<T>(shared: &Mutex<Shared<T>>) -> MutexGuard<'_, Shared<T>> {
+    shared.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Marks the stream as finished when the reading thread stops, even if it panics.
+struct Finish<T>(Arc<Mutex<Shared<T>>>);
+
+impl<T> Drop for Finish<T> {
+    fn drop(&mut self) {
+        let mut shared = lock(&self.0);
+        shared.finished = true;
+        shared.wake();
+    }
+}
+
+/// An asynchronous stream of events read from an [`EventSource`].
+///
+/// The stream yields the events in the order they are read. When reading fails, the error is
+/// yielded and the stream ends. [`close`](EventStream::close) cancels the reading, after which
+/// the stream yields the events that were already read and then ends; once it has ended, the
+/// reading thread has stopped and the source has been dropped. Dropping the stream cancels the
+/// reading as well.
+pub struct EventStream<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+    canceller: Box<dyn Cancel + Send + Sync>,
+    closed: bool,
+}
+
+impl<T: Send + 'static> EventStream<T> {
+    /// Start reading events from `source`.
+    pub fn new<S>(source: S) -> EventStream<T>
+    where
+        S: EventSource<Event = T>,
+    {
+        let shared = Arc::new(Mutex::new(Shared {
+            queue: VecDeque::new(),
+            waker: None,
+            finished: false,
+        }));
+        let canceller = Box::new(source.canceller());
+
+        let finish = Finish(shared.clone());
+        spawn_blocking(move || read_events(source, finish));
+
+        EventStream {
+            shared,
+            canceller,
+            closed: false,
+        }
+    }
+}
+
+#[cfg(windows)]
+impl EventStream<InputRecord> {
+    /// Create a stream of the events in the console input buffer.
+    pub fn console() -> io::Result<EventStream<InputRecord>> {
+        EventStream::with_handle(Handle::current_in_handle()?)
+    }
+
+    /// Create a stream of the events in the console input buffer of the given handle.
+    ///
+    /// The stream reads all input events from the handle, so nothing else should read from it
+    /// while the stream is open.
+    pub fn with_handle(handle: Handle) -> io::Result<EventStream<InputRecord>> {
+        Ok(EventStream::new(ConsoleInput {
+            console: Console::from(handle),
+            cancel: Semaphore::new()?,
+        }))
+    }
+}
+
+impl<T> EventStream<T> {
+    /// Stop reading events.
+    ///
+    /// The events that were already read are still yielded. Calling this more than once does
+    /// nothing.
+    pub fn close(&mut self) -> io::Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+        self.closed = true;
+        self.canceller.cancel()
+    }
+}
+
+impl<T> Stream for EventStream<T> {
+    type Item = io::Result<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut shared = lock(&self.shared);
+
+        if let Some(event) = shared.queue.pop_front() {
+            Poll::Ready(Some(event))
+        } else if shared.finished {
+            Poll::Ready(None)
+        } else {
+            shared.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl<T> Drop for EventStream<T> {
+    fn drop(&mut self) {
+        // There is nobody left to report an error to.
+        let _ = self.close();
+    }
+}
+
+/// Read events from `source` into the queue until the wait is cancelled or fails.
+fn read_events<S: EventSource>(mut source: S, finish: Finish<S::Event>) {
+    let error = loop {
+        match source.wait() {
+            Ok(true) => {}
+            Ok(false) => break None,
+            Err(error) => break Some(error),
+        }
+
+        match source.read() {
+            Ok(events) if events.is_empty() => {}
+            Ok(events) => {
+                let mut shared = lock(&finish.0);
+                shared.queue.extend(events.into_iter().map(Ok));
+                shared.wake();
+            }
+            Err(error) => break Some(error),
+        }
+    };
+
+    // Let go of the source before the stream is marked as finished.
+    drop(source);
+    if let Some(error) = error {
+        lock(&finish.0).queue.push_back(Err(error));
+    }
+}
+
+/// Run a function that blocks on a thread where that is allowed.
+fn spawn_blocking<F>(f: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    #[cfg(feature = "tokio")]
+    {
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            drop(runtime.spawn_blocking(f));
+            return;
+        }
+    }
+
+    #[cfg(feature = "async-std")]
+    drop(async_std::task::spawn_blocking(f));
+
+    #[cfg(not(feature = "async-std"))]
+    drop(thread::spawn(f));
+}
+
+/// The input buffer of the console, together with a semaphore to cancel waiting for it.
+#[cfg(windows)]
+struct ConsoleInput {
+    console: Console,
+    cancel: Semaphore,
+}
+
+#[cfg(windows)]
+impl EventSource for ConsoleInput {
+    type Event = InputRecord;
+    type Canceller = Semaphore;
+
+    fn wait(&mut self) -> io::Result<bool> {
+        Ok(self.console.poll_with_semaphore(&self.cancel, None)? != Wakeup::Semaphore)
+    }
+
+    fn read(&mut self) -> io::Result<Vec<InputRecord>> {
+        Ok(self.console.read_console_input()?)
+    }
+
+    fn canceller(&self) -> Semaphore {
+        self.cancel.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::io;
+    use std::pin::Pin;
+    use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+    use std::task::{Context, Poll, Wake};
+    use std::thread::{self, Thread};
+
+    use futures_core::Stream;
+
+    use super::{Cancel, EventSource, EventStream};
+
+    #[derive(Default)]
+    struct FakeState {
+        events: VecDeque<io::Result<u32>>,
+        cancelled: bool,
+        dropped: bool,
+    }
+
+    /// The side of a fake event source that the test pushes events into.
+    #[derive(Clone, Default)]
+    struct Fake(Arc<(Mutex<FakeState>, Condvar)>);
+
+    impl Fake {
+        fn state(&self) -> MutexGuard<'_, FakeState> {
+            self.0 .0.lock().unwrap()
+        }
+
+        fn push(&self, event: io::Result<u32>) {
+            self.state().events.push_back(event);
+            self.0 .1.notify_all();
+        }
+    }
+
+    impl Cancel for Fake {
+        fn cancel(&self) -> io::Result<()> {
+            self.state().cancelled = true;
+            self.0 .1.notify_all();
+            Ok(())
+        }
+    }
+
+    /// The side of a fake event source that the stream reads from.
+    struct FakeSource(Fake);
+
+    impl EventSource for FakeSource {
+        type Event = u32;
+        type Canceller = Fake;
+
+        fn wait(&mut self) -> io::Result<bool> {
+            let mut state = self.0.state();
+            loop {
+                if state.cancelled {
+                    return Ok(false);
+                }
+                if !state.events.is_empty() {
+                    return Ok(true);
+                }
+                state = self.0 .0 .1.wait(state).unwrap();
+            }
+        }
+
+        fn read(&mut self) -> io::Result<Vec<u32>> {
+            self.0.state().events.drain(..).collect()
+        }
+
+        fn canceller(&self) -> Fake {
+            self.0.clone()
+        }
+    }
+
+    impl Drop for FakeSource {
+        fn drop(&mut self) {
+            self.0.state().dropped = true;
+        }
+    }
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn start() -> (Fake, EventStream<u32>) {
+        let fake = Fake::default();
+        let stream = EventStream::new(FakeSource(fake.clone()));
+        (fake, stream)
+    }
+
+    fn next(stream: &mut EventStream<u32>) -> Option<io::Result<u32>> {
+        let waker = Arc::new(ThreadWaker(thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            match Pin::new(&mut *stream).poll_next(&mut cx) {
+                Poll::Ready(item) => return item,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_yields_events_in_order() {
+        let (fake, mut stream) = start();
+
+        fake.push(Ok(1));
+        assert_eq!(next(&mut stream).unwrap().unwrap(), 1);
+
+        fake.push(Ok(2));
+        fake.push(Ok(3));
+        assert_eq!(next(&mut stream).unwrap().unwrap(), 2);
+        assert_eq!(next(&mut stream).unwrap().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_close_ends_the_stream() {
+        let (fake, mut stream) = start();
+
+        fake.push(Ok(1));
+        assert_eq!(next(&mut stream).unwrap().unwrap(), 1);
+
+        stream.close().unwrap();
+        stream.close().unwrap();
+        assert!(next(&mut stream).is_none());
+        assert!(next(&mut stream).is_none());
+        assert!(fake.state().dropped);
+    }
+
+    #[test]
+    fn test_error_ends_the_stream() {
+        let (fake, mut stream) = start();
+
+        fake.push(Err(io::Error::new(io::ErrorKind::Other, "broken")));
+        let error = next(&mut stream).unwrap().unwrap_err();
+        assert_eq!(error.to_string(), "broken");
+        assert!(next(&mut stream).is_none());
+        assert!(fake.state().dropped);
+    }
+
+    #[test]
+    fn test_drop_stops_reading() {
+        let (fake, stream) = start();
+        drop(stream);
+
+        while !fake.state().dropped {
+            thread::yield_now();
+        }
+        assert!(fake.state().cancelled);
+    }
+}
@@ -0,0 +1,43 @@
+//! This module contains the logic for encoding the characters that fill console cells, which
+//! doesn't call into WinAPI, so it can be tested on every platform.
+
+use super::{Error, ErrorKind, Result};
+
+/// Encode a character as the single UTF-16 code unit that a console cell can hold.
+///
+/// Characters outside of the Basic Multilingual Plane need two code units (a surrogate pair), so
+/// they can't be used to fill cells and result in an error of kind
+/// [`InvalidInput`](ErrorKind::InvalidInput).
+pub fn encode_fill_char(c: char) -> Result<u16> {
+    let mut buf = [0; 2];
+    match *c.encode_utf16(&mut buf) {
+        [unit] => Ok(unit),
+        _ => Err(Error::new(
+            "FillConsoleOutputCharacterW",
+            ErrorKind::InvalidInput,
+            format!(
+                "character U+{:04X} lies outside of the Basic Multilingual Plane and can not fill a console cell",
+                c as u32
+            ),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::encode_fill_char;
+    use crate::ErrorKind;
+
+    #[test]
+    fn test_encode_fill_char() {
+        assert_eq!(encode_fill_char(' ').unwrap(), 0x0020);
+        assert_eq!(encode_fill_char('\u{2500}').unwrap(), 0x2500); // ─
+        assert_eq!(encode_fill_char('\u{2588}').unwrap(), 0x2588); // █
+        assert_eq!(encode_fill_char('\u{4E2D}').unwrap(), 0x4E2D); // 中
+        assert_eq!(encode_fill_char('\u{FFFD}').unwrap(), 0xFFFD);
+
+        let error = encode_fill_char('\u{1F600}').unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        assert!(error.to_string().contains("U+1F600"));
+    }
+}
@@ -1,111 +1,385 @@
 //! This module contains some logic for working with the console handle.
+//!
+//! [`HandleType`] is available on every platform, so errors can tell which handle they happened
+//! on anywhere.
 
-use std::io::Result;
+use std::fmt;
+#[cfg(windows)]
+use std::io;
+#[cfg(windows)]
+use std::mem::zeroed;
+#[cfg(windows)]
 use std::ops::Deref;
+#[cfg(windows)]
+use std::os::windows::io::{
+    AsHandle, AsRawHandle, BorrowedHandle, FromRawHandle, IntoRawHandle, OwnedHandle, RawHandle,
+};
+#[cfg(windows)]
 use std::ptr::null_mut;
+#[cfg(any(windows, test))]
 use std::sync::Arc;
+use std::sync::{PoisonError, RwLock};
 
-use winapi::shared::minwindef::DWORD;
+#[cfg(windows)]
+use winapi::shared::minwindef::{DWORD, FALSE};
+#[cfg(windows)]
 use winapi::um::{
-    fileapi::{CreateFileW, OPEN_EXISTING},
-    handleapi::{CloseHandle, INVALID_HANDLE_VALUE},
-    processenv::GetStdHandle,
-    winbase::{STD_INPUT_HANDLE, STD_OUTPUT_HANDLE},
-    winnt::{FILE_SHARE_READ, FILE_SHARE_WRITE, GENERIC_READ, GENERIC_WRITE, HANDLE},
+    consoleapi::GetConsoleMode,
+    errhandlingapi::SetLastError,
+    fileapi::{CreateFileW, GetFileType, OPEN_EXISTING},
+    handleapi::{CloseHandle, DuplicateHandle, INVALID_HANDLE_VALUE},
+    processenv::{GetStdHandle, SetStdHandle},
+    processthreadsapi::GetCurrentProcess,
+    winbase::{STD_ERROR_HANDLE, STD_INPUT_HANDLE, STD_OUTPUT_HANDLE},
+    wincon::{GetConsoleScreenBufferInfo, CONSOLE_SCREEN_BUFFER_INFO},
+    winnt::{
+        DUPLICATE_SAME_ACCESS, FILE_SHARE_READ, FILE_SHARE_WRITE, GENERIC_READ, GENERIC_WRITE,
+        HANDLE,
+    },
 };
 
-use super::handle_result;
+#[cfg(windows)]
+use super::win32::FILE_TYPE_UNKNOWN;
+use super::win32::{FILE_TYPE_CHAR, FILE_TYPE_DISK, FILE_TYPE_PIPE, FILE_TYPE_REMOTE};
+use super::Error;
+#[cfg(any(windows, test))]
+use super::Result;
+#[cfg(windows)]
+use super::{handle_result, result, ErrorKind};
 
 /// The standard handles of a process.
 ///
 /// See [the Windows documentation on console
 /// handles](https://docs.microsoft.com/en-us/windows/console/console-handles) for more info.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub enum HandleType {
     /// The process' standard output.
     OutputHandle,
     /// The process' standard input.
     InputHandle,
+    /// The process' standard error.
+    ErrorHandle,
     /// The process' active console screen buffer, `CONOUT$`.
     CurrentOutputHandle,
     /// The process' console input buffer, `CONIN$`.
     CurrentInputHandle,
 }
 
-/// Inner structure for closing a handle on Drop.
+impl fmt::Display for HandleType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HandleType::OutputHandle => "standard output handle",
+            HandleType::InputHandle => "standard input handle",
+            HandleType::ErrorHandle => "standard error handle",
+            HandleType::CurrentOutputHandle => "active screen buffer (CONOUT$)",
+            HandleType::CurrentInputHandle => "console input buffer (CONIN$)",
+        })
+    }
+}
+
+/// What kind of object a handle refers to, as determined by [`Handle::kind`].
+///
+/// When the standard output is redirected, its handle is a disk file or a pipe instead of a
+/// console, and the console functions fail on it.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum HandleKind {
+    /// A console input buffer, like `CONIN$`.
+    ConsoleInput,
+    /// A console screen buffer, like `CONOUT$`.
+    ConsoleOutput,
+    /// A file on a disk.
+    Disk,
+    /// A pipe or socket.
+    Pipe,
+    /// A character device that is not a console, like `NUL` or a serial port.
+    CharacterDevice,
+    /// Anything else.
+    Other,
+}
+
+impl HandleKind {
+    /// Classify a handle by the type returned for it by `GetFileType`, whether `GetConsoleMode`
+    /// succeeds on it, and whether `GetConsoleScreenBufferInfo` succeeds on it.
+    ///
+    /// Only character devices can be consoles; the results of the console functions are ignored
+    /// for other types.
+    pub fn classify(file_type: u32, has_console_mode: bool, is_screen_buffer: bool) -> HandleKind {
+        match file_type & !FILE_TYPE_REMOTE {
+            FILE_TYPE_CHAR if has_console_mode && is_screen_buffer => HandleKind::ConsoleOutput,
+            FILE_TYPE_CHAR if has_console_mode => HandleKind::ConsoleInput,
+            FILE_TYPE_CHAR => HandleKind::CharacterDevice,
+            FILE_TYPE_DISK => HandleKind::Disk,
+            FILE_TYPE_PIPE => HandleKind::Pipe,
+            _ => HandleKind::Other,
+        }
+    }
+
+    /// Get whether this is a console input or screen buffer.
+    pub fn is_console(self) -> bool {
+        matches!(self, HandleKind::ConsoleInput | HandleKind::ConsoleOutput)
+    }
+}
+
+/// The function that is called with the errors of closing handles when they are dropped.
+static CLOSE_ERROR_HOOK: RwLock<Option<fn(&Error)>> = RwLock::new(None);
+
+/// Set the function that is called with the error when closing a [`Handle`] fails while it is
+/// dropped, returning the function that was set before.
+///
+/// Dropping a handle never panics; without a hook, errors of closing it are ignored, and the
+/// handle may be leaked. Use [`Handle::close`] to handle the error of closing a handle at a
+/// certain point instead.
 ///
-/// The second parameter indicates if the HANDLE is exclusively owned or not.
-/// A non-exclusive handle can be created using for example
-/// `Handle::input_handle` or `Handle::output_handle`, which corresponds to
-/// stdin and stdout respectively.
+/// The function is also called when a dropped guard fails to undo its change, like freeing the
+/// console of a [`ProcessConsole`](crate::ProcessConsole), or restoring the title of a
+/// [`TitleGuard`](crate::TitleGuard) or the code pages of a
+/// [`CodePageGuard`](crate::CodePageGuard).
+pub fn set_close_error_hook(hook: Option<fn(&Error)>) -> Option<fn(&Error)> {
+    let mut current = CLOSE_ERROR_HOOK
+        .write()
+        .unwrap_or_else(PoisonError::into_inner);
+    std::mem::replace(&mut *current, hook)
+}
+
+#[cfg(any(windows, test))]
+pub(crate) fn report_close_error(error: &Error) {
+    let hook = *CLOSE_ERROR_HOOK
+        .read()
+        .unwrap_or_else(PoisonError::into_inner);
+    if let Some(hook) = hook {
+        hook(error);
+    }
+}
+
+#[cfg(any(windows, test))]
+/// Inner structure for closing a handle on Drop, shared by all clones of a `Handle`.
+///
+/// `owned` tells whether the handle is closed on drop. It is false for borrowed handles, like
+/// the standard handles of `Handle::input_handle` or `Handle::output_handle`, and is cleared
+/// when the handle is closed or given away, so it is closed at most once.
+///
+/// The structure is generic over the handle and the function that closes it, so the bookkeeping
+/// can be tested with a fake.
 #[derive(Debug)]
-struct Inner {
-    handle: HANDLE,
-    is_exclusive: bool,
+struct Inner<T: Copy + fmt::Debug> {
+    handle: T,
+    owned: bool,
+    close: fn(T) -> Result<()>,
 }
 
-impl Inner {
-    fn new_exclusive(handle: HANDLE) -> Self {
+#[cfg(any(windows, test))]
+impl<T: Copy + fmt::Debug> Inner<T> {
+    fn new(handle: T, owned: bool, close: fn(T) -> Result<()>) -> Self {
         Inner {
             handle,
-            is_exclusive: true,
+            owned,
+            close,
         }
     }
 
-    fn new_shared(handle: HANDLE) -> Self {
-        Inner {
-            handle,
-            is_exclusive: false,
+    /// Close the handle if it is owned.
+    fn close(mut self) -> Result<()> {
+        let owned = std::mem::replace(&mut self.owned, false);
+        if owned {
+            (self.close)(self.handle)
+        } else {
+            Ok(())
         }
     }
+
+    /// Give up ownership of the handle without closing it.
+    fn release(mut self) -> T {
+        self.owned = false;
+        self.handle
+    }
 }
 
-impl Drop for Inner {
+#[cfg(any(windows, test))]
+impl<T: Copy + fmt::Debug> Drop for Inner<T> {
     fn drop(&mut self) {
-        if self.is_exclusive {
-            assert!(
-                unsafe { CloseHandle(self.handle) != 0 },
-                "failed to close handle"
-            )
+        if self.owned {
+            if let Err(error) = (self.close)(self.handle) {
+                report_close_error(&error.with_argument("handle", &self.handle));
+            }
         }
     }
 }
 
-unsafe impl Send for Inner {}
+#[cfg(any(windows, test))]
+/// Close the handle if this is the last reference to it, reporting the error of closing it.
+fn close_last<T: Copy + fmt::Debug>(inner: Arc<Inner<T>>) -> Result<()> {
+    match Arc::try_unwrap(inner) {
+        Ok(inner) => inner.close(),
+        Err(_) => Ok(()),
+    }
+}
+
+#[cfg(any(windows, test))]
+/// Take the handle out if this is the last reference to an owned handle.
+fn release_last<T: Copy + fmt::Debug>(
+    inner: Arc<Inner<T>>,
+) -> std::result::Result<T, Arc<Inner<T>>> {
+    if !inner.owned {
+        return Err(inner);
+    }
+    Arc::try_unwrap(inner).map(Inner::release)
+}
+
+/// A raw handle that can be sent to other threads, because most handles are thread-safe.
+#[cfg(windows)]
+#[derive(Clone, Copy)]
+struct RawHandleValue(HANDLE);
+
+#[cfg(windows)]
+impl fmt::Debug for RawHandleValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(windows)]
+unsafe impl Send for RawHandleValue {}
+
+#[cfg(windows)]
+unsafe impl Sync for RawHandleValue {}
+
+#[cfg(windows)]
+impl Inner<RawHandleValue> {
+    fn new_owned(handle: HANDLE) -> Self {
+        Inner::new(RawHandleValue(handle), true, close_handle)
+    }
+
+    fn new_borrowed(handle: HANDLE) -> Self {
+        Inner::new(RawHandleValue(handle), false, close_handle)
+    }
+}
 
-unsafe impl Sync for Inner {}
+/// This wraps
+/// [`CloseHandle`](https://docs.microsoft.com/en-us/windows/win32/api/handleapi/nf-handleapi-closehandle).
+#[cfg(windows)]
+fn close_handle(handle: RawHandleValue) -> Result<()> {
+    result("CloseHandle", unsafe { CloseHandle(handle.0) })
+}
 
 /// This abstracts away some WinAPI calls to set and get some console handles.
 ///
-/// It wraps WinAPI's [`HANDLE`] type.
+/// It wraps WinAPI's [`HANDLE`] type. It converts from and to the handle types of
+/// [`std::os::windows::io`], like [`OwnedHandle`] and [`BorrowedHandle`].
+///
+/// # Ownership
+///
+/// Every handle is either owned or borrowed, which is fixed when it is created and is told by
+/// [`Handle::is_owned`]:
+///
+/// - Owned handles come from functions that open a new handle, like
+///   [`Handle::current_out_handle`] and [`Handle::duplicate`], and from [`Handle::from_raw`] and
+///   [`OwnedHandle`]. The handle is closed exactly once, when the last clone is dropped or passed
+///   to [`Handle::close`], unless it was taken back with [`Handle::into_owned`] or
+///   [`Handle::into_raw`] before.
+/// - Borrowed handles come from the standard handles, like [`Handle::output_handle`], which
+///   belong to the process, and from [`Handle::borrow_raw`] and [`BorrowedHandle`]. They are
+///   never closed.
+///
+/// Clones share the handle instead of each owning it, because most wrappers of this crate are
+/// cloned freely and closing the handle while a clone still uses it would leave that clone with
+/// a handle value that may already belong to another object. Whether a shared handle is closed
+/// is therefore recorded next to it rather than in its type. Where the type should tell, convert
+/// into an [`OwnedHandle`] with [`Handle::into_owned`] or borrow a [`BorrowedHandle`] through
+/// [`AsHandle`].
+#[cfg(windows)]
 #[derive(Debug, Clone)]
 pub struct Handle {
-    handle: Arc<Inner>,
+    handle: Arc<Inner<RawHandleValue>>,
+    handle_type: Option<HandleType>,
 }
 
+#[cfg(windows)]
 impl Handle {
     /// Create a new handle of a certaint type.
     pub fn new(handle: HandleType) -> Result<Handle> {
         match handle {
             HandleType::OutputHandle => Handle::output_handle(),
             HandleType::InputHandle => Handle::input_handle(),
+            HandleType::ErrorHandle => Handle::error_handle(),
             HandleType::CurrentOutputHandle => Handle::current_out_handle(),
             HandleType::CurrentInputHandle => Handle::current_in_handle(),
         }
     }
 
-    /// Construct a handle from a raw handle.
+    /// Construct a handle from a raw handle, taking ownership of it.
     ///
     /// # Safety
     ///
     /// This is unsafe since there is not guarantee that the underlying HANDLE is thread-safe to implement `Send` and `Sync`.
-    /// Most HANDLE's however, are thread safe.
+    /// Most HANDLE's however, are thread safe. The handle must be open and not owned by anything
+    /// else, because it is closed when the last clone is dropped.
     pub unsafe fn from_raw(handle: HANDLE) -> Self {
         Self {
-            handle: Arc::new(Inner::new_exclusive(handle)),
+            handle: Arc::new(Inner::new_owned(handle)),
+            handle_type: None,
+        }
+    }
+
+    /// Construct a handle from a raw handle that is owned elsewhere, and is never closed by this
+    /// handle.
+    ///
+    /// # Safety
+    ///
+    /// The handle must be thread-safe like for [`Handle::from_raw`], and must stay open for as
+    /// long as this handle or any of its clones exist.
+    pub unsafe fn borrow_raw(handle: HANDLE) -> Self {
+        Self {
+            handle: Arc::new(Inner::new_borrowed(handle)),
+            handle_type: None,
         }
     }
 
+    /// Get whether this handle owns the underlying handle, so it is closed when the last clone
+    /// is dropped.
+    pub fn is_owned(&self) -> bool {
+        self.handle.owned
+    }
+
+    /// Take back ownership of the underlying handle, without closing it.
+    ///
+    /// This only succeeds for an owned handle without any other clones, otherwise the handle is
+    /// returned as is.
+    pub fn into_owned(self) -> std::result::Result<OwnedHandle, Handle> {
+        let handle_type = self.handle_type;
+        match release_last(self.handle) {
+            Ok(raw) => Ok(unsafe { OwnedHandle::from_raw_handle(raw.0 as RawHandle) }),
+            Err(handle) => Err(Handle {
+                handle,
+                handle_type,
+            }),
+        }
+    }
+
+    /// Close the handle, reporting if that fails.
+    ///
+    /// Only an owned handle is closed, and only when this is its last clone; otherwise this
+    /// just drops this clone. Dropping a handle closes it as well, but can't report failure; see
+    /// [`set_close_error_hook`](crate::set_close_error_hook).
+    ///
+    /// This wraps
+    /// [`CloseHandle`](https://docs.microsoft.com/en-us/windows/win32/api/handleapi/nf-handleapi-closehandle).
+    pub fn close(self) -> Result<()> {
+        let handle_type = self.handle_type;
+        close_last(self.handle).map_err(|e| match handle_type {
+            Some(handle_type) => e.with_handle_type(handle_type),
+            None => e,
+        })
+    }
+
+    /// Take back ownership of the underlying raw handle, without closing it.
+    ///
+    /// Like [`Handle::into_owned`], this only succeeds for an owned handle without any other
+    /// clones.
+    pub fn into_raw(self) -> std::result::Result<HANDLE, Handle> {
+        self.into_owned()
+            .map(|owned| owned.into_raw_handle() as HANDLE)
+    }
+
     /// Get the handle of the active screen buffer.
     /// When using multiple screen buffers this will always point to the to the current screen output buffer.
     ///
@@ -117,7 +391,7 @@ impl Handle {
         let utf16: Vec<u16> = "CONOUT$\0".encode_utf16().collect();
         let utf16_ptr: *const u16 = utf16.as_ptr();
 
-        let handle = handle_result(unsafe {
+        let handle = handle_result("CreateFileW", unsafe {
             CreateFileW(
                 utf16_ptr,
                 GENERIC_READ | GENERIC_WRITE,
@@ -127,10 +401,12 @@ impl Handle {
                 0,
                 null_mut(),
             )
-        })?;
+        })
+        .map_err(|e| e.with_handle_type(HandleType::CurrentOutputHandle))?;
 
         Ok(Handle {
-            handle: Arc::new(Inner::new_exclusive(handle)),
+            handle: Arc::new(Inner::new_owned(handle)),
+            handle_type: Some(HandleType::CurrentOutputHandle),
         })
     }
 
@@ -144,7 +420,7 @@ impl Handle {
         let utf16: Vec<u16> = "CONIN$\0".encode_utf16().collect();
         let utf16_ptr: *const u16 = utf16.as_ptr();
 
-        let handle = handle_result(unsafe {
+        let handle = handle_result("CreateFileW", unsafe {
             CreateFileW(
                 utf16_ptr,
                 GENERIC_READ | GENERIC_WRITE,
@@ -154,10 +430,12 @@ impl Handle {
                 0,
                 null_mut(),
             )
-        })?;
+        })
+        .map_err(|e| e.with_handle_type(HandleType::CurrentInputHandle))?;
 
         Ok(Handle {
-            handle: Arc::new(Inner::new_exclusive(handle)),
+            handle: Arc::new(Inner::new_owned(handle)),
+            handle_type: Some(HandleType::CurrentInputHandle),
         })
     }
 
@@ -168,7 +446,7 @@ impl Handle {
     /// This wraps [`GetStdHandle`](https://docs.microsoft.com/en-us/windows/console/getstdhandle)
     /// called with `STD_OUTPUT_HANDLE`.
     pub fn output_handle() -> Result<Handle> {
-        Self::std_handle(STD_OUTPUT_HANDLE)
+        Self::std_handle(STD_OUTPUT_HANDLE, HandleType::OutputHandle)
     }
 
     /// Get the handle of the input screen buffer.
@@ -178,17 +456,140 @@ impl Handle {
     /// This wraps [`GetStdHandle`](https://docs.microsoft.com/en-us/windows/console/getstdhandle)
     /// called with `STD_INPUT_HANDLE`.
     pub fn input_handle() -> Result<Handle> {
-        Self::std_handle(STD_INPUT_HANDLE)
+        Self::std_handle(STD_INPUT_HANDLE, HandleType::InputHandle)
+    }
+
+    /// Get the handle of the standard error.
+    ///
+    /// On success this function returns the `HANDLE` to `STD_ERROR_HANDLE`.
+    ///
+    /// This wraps [`GetStdHandle`](https://docs.microsoft.com/en-us/windows/console/getstdhandle)
+    /// called with `STD_ERROR_HANDLE`.
+    pub fn error_handle() -> Result<Handle> {
+        Self::std_handle(STD_ERROR_HANDLE, HandleType::ErrorHandle)
+    }
+
+    /// Make `handle` the standard handle of type `std`, which has to be
+    /// [`OutputHandle`](HandleType::OutputHandle), [`InputHandle`](HandleType::InputHandle) or
+    /// [`ErrorHandle`](HandleType::ErrorHandle).
+    ///
+    /// This doesn't transfer ownership: `handle` has to stay open for as long as it is the
+    /// standard handle, for example by keeping a clone of it.
+    ///
+    /// This wraps [`SetStdHandle`](https://docs.microsoft.com/en-us/windows/console/setstdhandle).
+    pub fn set_std(std: HandleType, handle: &Handle) -> Result<()> {
+        let which_std = match std {
+            HandleType::OutputHandle => STD_OUTPUT_HANDLE,
+            HandleType::InputHandle => STD_INPUT_HANDLE,
+            HandleType::ErrorHandle => STD_ERROR_HANDLE,
+            HandleType::CurrentOutputHandle | HandleType::CurrentInputHandle => {
+                return Err(Error::new(
+                    "SetStdHandle",
+                    ErrorKind::InvalidInput,
+                    format!("the {} is not a standard handle", std),
+                ));
+            }
+        };
+
+        result("SetStdHandle", unsafe { SetStdHandle(which_std, **handle) })
+            .map_err(|e| e.with_handle_type(std))
+    }
+
+    /// Duplicate the handle, so that the duplicate is owned independently of this handle.
+    ///
+    /// This turns a shared handle, like the standard output, into one that is closed when the
+    /// duplicate is dropped, without closing the original. The duplicate has the same access
+    /// rights and [`HandleType`].
+    ///
+    /// This wraps
+    /// [`DuplicateHandle`](https://docs.microsoft.com/en-us/windows/win32/api/handleapi/nf-handleapi-duplicatehandle).
+    pub fn duplicate(&self) -> Result<Handle> {
+        let mut duplicate = null_mut();
+        result("DuplicateHandle", unsafe {
+            let process = GetCurrentProcess();
+            DuplicateHandle(
+                process,
+                **self,
+                process,
+                &mut duplicate,
+                0,
+                FALSE,
+                DUPLICATE_SAME_ACCESS,
+            )
+        })
+        .map_err(|e| e.with_handle(self))?;
+
+        Ok(Handle {
+            handle: Arc::new(Inner::new_owned(duplicate)),
+            handle_type: self.handle_type,
+        })
     }
 
-    fn std_handle(which_std: DWORD) -> Result<Handle> {
-        let handle = handle_result(unsafe { GetStdHandle(which_std) })?;
+    fn std_handle(which_std: DWORD, handle_type: HandleType) -> Result<Handle> {
+        let handle = handle_result("GetStdHandle", unsafe { GetStdHandle(which_std) })
+            .map_err(|e| e.with_handle_type(handle_type))?;
 
         Ok(Handle {
-            handle: Arc::new(Inner::new_shared(handle)),
+            handle: Arc::new(Inner::new_borrowed(handle)),
+            handle_type: Some(handle_type),
         })
     }
 
+    /// Get which of the standard handles this is, if it was created as one of them.
+    ///
+    /// Handles constructed with [`Handle::from_raw`] are of no known type.
+    pub fn handle_type(&self) -> Option<HandleType> {
+        self.handle_type
+    }
+
+    /// Get what kind of object this handle refers to.
+    ///
+    /// This wraps
+    /// [`GetFileType`](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getfiletype),
+    /// and for character devices
+    /// [`GetConsoleMode`](https://docs.microsoft.com/en-us/windows/console/getconsolemode) and
+    /// [`GetConsoleScreenBufferInfo`](https://docs.microsoft.com/en-us/windows/console/getconsolescreenbufferinfo)
+    /// to tell consoles apart.
+    pub fn kind(&self) -> Result<HandleKind> {
+        // `GetFileType` returns `FILE_TYPE_UNKNOWN` both on failure and for unknown types, which
+        // can only be told apart by the last error.
+        let file_type = unsafe {
+            SetLastError(0);
+            GetFileType(**self)
+        };
+        if file_type == FILE_TYPE_UNKNOWN {
+            match io::Error::last_os_error().raw_os_error() {
+                Some(0) | None => {}
+                Some(code) => {
+                    return Err(Error::from_code("GetFileType", code as u32).with_handle(self))
+                }
+            }
+        }
+
+        let mut has_console_mode = false;
+        let mut is_screen_buffer = false;
+        if file_type & !FILE_TYPE_REMOTE == FILE_TYPE_CHAR {
+            let mut mode = 0;
+            has_console_mode = unsafe { GetConsoleMode(**self, &mut mode) } != 0;
+
+            let mut csbi: CONSOLE_SCREEN_BUFFER_INFO = unsafe { zeroed() };
+            is_screen_buffer =
+                has_console_mode && unsafe { GetConsoleScreenBufferInfo(**self, &mut csbi) } != 0;
+        }
+
+        Ok(HandleKind::classify(
+            file_type,
+            has_console_mode,
+            is_screen_buffer,
+        ))
+    }
+
+    /// Get whether this handle is a console input or screen buffer, so the console functions
+    /// work on it. A handle that can't be classified is not a console.
+    pub fn is_console(&self) -> bool {
+        self.kind().map_or(false, HandleKind::is_console)
+    }
+
     /// Checks if the console handle is an invalid handle value.
     ///
     /// This is done by checking if the passed `HANDLE` is equal to `INVALID_HANDLE_VALUE`.
@@ -197,23 +598,225 @@ impl Handle {
     }
 }
 
+#[cfg(windows)]
+impl From<OwnedHandle> for Handle {
+    fn from(handle: OwnedHandle) -> Self {
+        unsafe { Handle::from_raw(handle.into_raw_handle() as HANDLE) }
+    }
+}
+
+#[cfg(windows)]
+impl From<BorrowedHandle<'static>> for Handle {
+    fn from(handle: BorrowedHandle<'static>) -> Self {
+        unsafe { Handle::borrow_raw(handle.as_raw_handle() as HANDLE) }
+    }
+}
+
+#[cfg(windows)]
+impl AsRawHandle for Handle {
+    fn as_raw_handle(&self) -> RawHandle {
+        self.handle.handle.0 as RawHandle
+    }
+}
+
+#[cfg(windows)]
+impl AsHandle for Handle {
+    fn as_handle(&self) -> BorrowedHandle<'_> {
+        // The handle stays open for as long as `self` exists.
+        unsafe { BorrowedHandle::borrow_raw(self.as_raw_handle()) }
+    }
+}
+
+#[cfg(windows)]
 impl Deref for Handle {
     type Target = HANDLE;
 
     fn deref(&self) -> &HANDLE {
-        &self.handle.handle
+        &self.handle.handle.0
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::cell::RefCell;
+    use std::sync::Arc;
+
+    use super::{close_last, release_last, set_close_error_hook, HandleKind, Inner};
+    #[cfg(windows)]
     use super::{Handle, HandleType};
+    use crate::{Error, Result};
+
+    /// The handle that the fake close function fails to close.
+    const BROKEN: u32 = 13;
+
+    thread_local! {
+        static CLOSED: RefCell<Vec<u32>> = const { RefCell::new(Vec::new()) };
+        static REPORTED: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    fn fake_close(handle: u32) -> Result<()> {
+        CLOSED.with(|closed| closed.borrow_mut().push(handle));
+        if handle == BROKEN {
+            Err(Error::from_code("CloseHandle", 6))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn closed() -> Vec<u32> {
+        CLOSED.with(|closed| closed.borrow_mut().drain(..).collect())
+    }
+
+    #[test]
+    fn test_classify() {
+        let cases = [
+            (0x0002, true, true, HandleKind::ConsoleOutput),
+            (0x0002, true, false, HandleKind::ConsoleInput),
+            (0x0002, false, false, HandleKind::CharacterDevice),
+            (0x0002, false, true, HandleKind::CharacterDevice),
+            (0x0001, false, false, HandleKind::Disk),
+            (0x8001, false, false, HandleKind::Disk),
+            (0x0003, false, false, HandleKind::Pipe),
+            // Console probes are only trusted for character devices.
+            (0x0003, true, true, HandleKind::Pipe),
+            (0x0000, false, false, HandleKind::Other),
+        ];
+        for &(file_type, has_console_mode, is_screen_buffer, kind) in &cases {
+            assert_eq!(
+                HandleKind::classify(file_type, has_console_mode, is_screen_buffer),
+                kind,
+                "file type {:#x}, console mode {}, screen buffer {}",
+                file_type,
+                has_console_mode,
+                is_screen_buffer
+            );
+        }
+
+        assert!(HandleKind::ConsoleInput.is_console());
+        assert!(HandleKind::ConsoleOutput.is_console());
+        assert!(!HandleKind::Pipe.is_console());
+    }
+
+    #[test]
+    fn test_drop_closes_owned_handles() {
+        drop(Inner::new(1, true, fake_close));
+        drop(Inner::new(2, false, fake_close));
+        assert_eq!(closed(), [1]);
+    }
+
+    #[test]
+    fn test_close_last_reference() {
+        let inner = Arc::new(Inner::new(3, true, fake_close));
+        let clone = Arc::clone(&inner);
+        close_last(inner).unwrap();
+        assert_eq!(closed(), []);
+        close_last(clone).unwrap();
+        assert_eq!(closed(), [3]);
+
+        close_last(Arc::new(Inner::new(4, false, fake_close))).unwrap();
+        assert_eq!(closed(), []);
+    }
+
+    #[test]
+    fn test_close_reports_error_once() {
+        let error = close_last(Arc::new(Inner::new(BROKEN, true, fake_close))).unwrap_err();
+        assert_eq!(error.api(), "CloseHandle");
+        // Dropping doesn't try to close the handle again.
+        assert_eq!(closed(), [BROKEN]);
+    }
 
+    #[test]
+    fn test_release_last_reference() {
+        let borrowed = Arc::new(Inner::new(5, false, fake_close));
+        assert!(release_last(borrowed).is_err());
+
+        let inner = Arc::new(Inner::new(6, true, fake_close));
+        let clone = Arc::clone(&inner);
+        let inner = release_last(inner).unwrap_err();
+        drop(clone);
+        assert_eq!(release_last(inner).unwrap(), 6);
+        assert_eq!(closed(), []);
+    }
+
+    #[test]
+    fn test_drop_reports_to_hook() {
+        fn record(error: &Error) {
+            REPORTED.with(|reported| reported.borrow_mut().push(error.to_string()));
+        }
+
+        let previous = set_close_error_hook(Some(record));
+        drop(Inner::new(BROKEN, true, fake_close));
+        set_close_error_hook(previous);
+
+        assert_eq!(closed(), [BROKEN]);
+        REPORTED.with(|reported| {
+            assert_eq!(
+                *reported.borrow(),
+                ["CloseHandle failed (handle: 13): the handle is invalid (os error 6)"]
+            );
+        });
+    }
+
+    #[cfg(windows)]
     #[test]
     fn test_get_handle() {
         assert!(Handle::new(HandleType::OutputHandle).is_ok());
         assert!(Handle::new(HandleType::InputHandle).is_ok());
+        assert!(Handle::new(HandleType::ErrorHandle).is_ok());
         assert!(Handle::new(HandleType::CurrentOutputHandle).is_ok());
         assert!(Handle::new(HandleType::CurrentInputHandle).is_ok());
     }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_ownership() {
+        let handle = Handle::current_out_handle().unwrap();
+        assert!(handle.is_owned());
+
+        let clone = handle.clone();
+        let handle = handle.into_owned().unwrap_err();
+        drop(clone);
+        let handle = Handle::from(handle.into_owned().unwrap());
+        assert!(handle.is_owned());
+
+        let borrowed = unsafe { Handle::borrow_raw(*handle) };
+        assert!(!borrowed.is_owned());
+        assert!(borrowed.into_raw().is_err());
+        assert!(!Handle::output_handle().unwrap().is_owned());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_kind() {
+        let output = Handle::current_out_handle().unwrap();
+        assert_eq!(output.kind().unwrap(), HandleKind::ConsoleOutput);
+        assert!(output.is_console());
+
+        let input = Handle::current_in_handle().unwrap();
+        assert_eq!(input.kind().unwrap(), HandleKind::ConsoleInput);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_duplicate_and_set_std() {
+        let error = Handle::error_handle().unwrap();
+        let duplicate = error.duplicate().unwrap();
+        assert!(duplicate.is_owned());
+        assert_eq!(duplicate.handle_type(), Some(HandleType::ErrorHandle));
+        drop(duplicate);
+
+        Handle::set_std(HandleType::ErrorHandle, &error).unwrap();
+        assert!(Handle::set_std(HandleType::CurrentOutputHandle, &error).is_err());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_close() {
+        let handle = Handle::current_out_handle().unwrap();
+        let clone = handle.clone();
+        handle.close().unwrap();
+        clone.close().unwrap();
+
+        Handle::output_handle().unwrap().close().unwrap();
+    }
 }
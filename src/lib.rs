@@ -1,73 +1,143 @@
-#![cfg(windows)]
 #![deny(unused_imports)]
 
-use std::io;
+//! The plain data types of this crate, like [`Coord`] and [`ScreenBufferInfo`], are available on
+//! every platform so code working with them can be tested anywhere. Everything that calls into
+//! WinAPI is only available on Windows.
 
+#[cfg(windows)]
 use winapi::shared::minwindef::BOOL;
-use winapi::um::handleapi::INVALID_HANDLE_VALUE;
+#[cfg(windows)]
 use winapi::um::wincontypes::COORD;
+#[cfg(windows)]
 use winapi::um::winnt::HANDLE;
 
+#[cfg(windows)]
 pub use self::{
     cfi::FontInfo,
-    console::Console,
+    code_page::{
+        input_code_page, output_code_page, set_input_code_page, set_output_code_page, CodePageGuard,
+    },
+    console::{Console, Wakeup},
     console_mode::ConsoleMode,
-    csbi::ScreenBufferInfo,
-    handle::{Handle, HandleType},
+    event::{Event, EventBuilder},
+    handle::Handle,
+    mutex::{Mutex, MutexGuard},
+    process_console::ProcessConsole,
+    pseudo_console::PseudoConsole,
     screen_buffer::ScreenBuffer,
-    semaphore::Semaphore,
+    semaphore::{Semaphore, SemaphoreBuilder},
+    structs::{ButtonState, EventFlags, InputRecord, MouseEvent},
+    title::{original_title, set_title, title, TitleGuard},
+    wait::{wait_all, wait_any, Waitable},
+    waitable_timer::WaitableTimer,
+};
+pub use self::{
+    cfi_ex::FontInfoEx,
+    code_page::{CodePage, CodecError},
+    convention::{convert, Convention, NonNullHandle, NonZero, NonZeroCoord, ValidHandle},
+    csbi::ScreenBufferInfo,
+    csbi_ex::ScreenBufferInfoEx,
+    error::{Error, ErrorKind, Result},
+    event_stream::{Cancel, EventSource, EventStream},
+    fill_char::encode_fill_char,
+    handle::{set_close_error_hook, HandleKind, HandleType},
+    line_editor::{EditOutcome, LineBuffer, LineEditor},
+    pixel::{Pixel, PixelMapping},
+    process_console::ProcessConsoleError,
+    resize::{ResizeError, ResizePlan, ResizeStep},
+    semaphore::{LocalSemaphore, SemaphoreError},
     structs::{
-        ButtonState, ControlKeyState, Coord, EventFlags, InputRecord, KeyEventRecord, MouseEvent,
-        Size, WindowPositions,
+        Attributes, ColorTable, ControlKeyState, ConversionError, Coord, KeyEventRecord, Rgb, Size,
+        WindowPositions,
+    },
+    utf8_writer::{Utf8Writer, WriteUtf16},
+    wait::{timeout_millis, wait_result, WaitResult},
+    width::{
+        char_width, cursor_after_write, pad_to_width, str_width, truncate_to_width, wrap_to_width,
     },
 };
 
+#[cfg(windows)]
 mod cfi;
+mod cfi_ex;
+mod code_page;
+#[cfg(windows)]
 mod console;
+#[cfg(windows)]
 mod console_mode;
+mod convention;
 mod csbi;
+mod csbi_ex;
+mod error;
+#[cfg(windows)]
+mod event;
+mod event_stream;
+mod fill_char;
 mod handle;
+mod line_editor;
+#[cfg(windows)]
+mod mutex;
+mod pixel;
+mod process_console;
+mod pseudo_console;
+mod resize;
+#[cfg(windows)]
 mod screen_buffer;
 mod semaphore;
 mod structs;
+mod title;
+mod utf8_writer;
+mod wait;
+#[cfg(windows)]
+mod waitable_timer;
+mod width;
+mod win32;
 
-/// Get the result of a call to WinAPI as an [`io::Result`].
+/// Get the result of a call to the WinAPI function `api` that returns a `BOOL`, which is zero
+/// on failure.
+#[cfg(windows)]
 #[inline]
-pub fn result(return_value: BOOL) -> io::Result<()> {
-    if return_value != 0 {
-        Ok(())
-    } else {
-        Err(io::Error::last_os_error())
-    }
+pub fn result(api: &'static str, return_value: BOOL) -> Result<()> {
+    convention::check(NonZero, api, return_value)
 }
 
-/// Get the result of a call to WinAPI that returns a
-/// [`COORD`](https://docs.microsoft.com/en-us/windows/console/coord-str) as an [`io::Result`].
+/// Get the result of a call to the WinAPI function `api` that returns a
+/// [`COORD`](https://docs.microsoft.com/en-us/windows/console/coord-str), which is `(0, 0)` on
+/// failure.
+#[cfg(windows)]
 #[inline]
-pub fn coord_result(return_value: COORD) -> io::Result<Coord> {
-    if return_value.X != 0 && return_value.Y != 0 {
-        Ok(Coord::from(return_value))
-    } else {
-        Err(io::Error::last_os_error())
-    }
+pub fn coord_result(api: &'static str, return_value: COORD) -> Result<Coord> {
+    convention::check(NonZeroCoord, api, Coord::from(return_value))
 }
 
-/// Get the result of a call to WinAPI that returns a handle or `INVALID_HANDLE_VALUE`.
+/// Get the result of a call to the WinAPI function `api` that returns a handle, which is
+/// `INVALID_HANDLE_VALUE` on failure.
+#[cfg(windows)]
 #[inline]
-pub fn handle_result(return_value: HANDLE) -> io::Result<HANDLE> {
-    if return_value != INVALID_HANDLE_VALUE {
-        Ok(return_value)
-    } else {
-        Err(io::Error::last_os_error())
-    }
+pub fn handle_result(api: &'static str, return_value: HANDLE) -> Result<HANDLE> {
+    convention::check(ValidHandle, api, return_value)
 }
 
-/// Get the result of a call to WinAPI that returns a handle or `NULL`.
+/// Get the result of a call to the WinAPI function `api` that returns a handle, which is `NULL`
+/// on failure.
+#[cfg(windows)]
 #[inline]
-pub fn nonnull_handle_result(return_value: HANDLE) -> io::Result<HANDLE> {
-    if return_value.is_null() {
-        Err(io::Error::last_os_error())
-    } else {
-        Ok(return_value)
+pub fn nonnull_handle_result(api: &'static str, return_value: HANDLE) -> Result<HANDLE> {
+    convention::check(NonNullHandle, api, return_value)
+}
+
+/// Encode a string as the NUL terminated UTF-16 string that the wide WinAPI function `api` takes.
+///
+/// Strings that contain a NUL character result in an error of kind
+/// [`InvalidInput`](ErrorKind::InvalidInput), because WinAPI would cut them off there.
+#[cfg(windows)]
+pub(crate) fn wide_nul(api: &'static str, s: &str) -> Result<Vec<u16>> {
+    if s.contains('\0') {
+        return Err(Error::new(
+            api,
+            ErrorKind::InvalidInput,
+            "string contains a NUL character",
+        ));
     }
+    Ok(s.encode_utf16().chain(std::iter::once(0)).collect())
 }
@@ -0,0 +1,837 @@
+//! This module contains a line editor for reading a line of text from the console, with history,
+//! cursor movement and word deletion.
+//!
+//! All editing is done by [`LineEditor::handle_key`], which only looks at [`KeyEventRecord`]s, so
+//! it works without a console. On Windows, [`LineEditor::read_line`] feeds it the key events of
+//! the console and draws the line into the screen buffer.
+
+#[cfg(windows)]
+use std::io::{self, Write};
+
+#[cfg(windows)]
+use winapi::um::wincon::ENABLE_PROCESSED_INPUT;
+
+use super::width::{char_width, clusters};
+use super::win32::{
+    LEFT_ALT_PRESSED, LEFT_CTRL_PRESSED, RIGHT_ALT_PRESSED, RIGHT_CTRL_PRESSED, VK_BACK, VK_DELETE,
+    VK_DOWN, VK_END, VK_ESCAPE, VK_HOME, VK_LEFT, VK_RETURN, VK_RIGHT, VK_UP,
+};
+use super::KeyEventRecord;
+#[cfg(windows)]
+use super::{
+    cursor_after_write, Console, ConsoleMode, Coord, Handle, InputRecord, ScreenBuffer, Size,
+    Utf8Writer,
+};
+
+/// The number of lines a [`LineEditor`] remembers by default.
+const DEFAULT_HISTORY_LIMIT: usize = 500;
+
+/// The text of a line that is being edited, and the position of the cursor in it.
+///
+/// The cursor is a byte offset into the text. Moving and deleting works on a character together
+/// with the combining marks that follow it, so these are never split up.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LineBuffer {
+    text: String,
+    cursor: usize,
+}
+
+impl LineBuffer {
+    /// Create an empty buffer.
+    pub fn new() -> LineBuffer {
+        LineBuffer::default()
+    }
+
+    /// Create a buffer containing `text`, with the cursor at the end.
+    pub fn with_text(text: &str) -> LineBuffer {
+        LineBuffer {
+            text: text.to_owned(),
+            cursor: text.len(),
+        }
+    }
+
+    /// Get the text of the line.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Get the position of the cursor, as a byte offset into the text.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Get whether the line is empty.
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Replace the text of the line and move the cursor to the end.
+    pub fn set_text(&mut self, text: &str) {
+        self.text.clear();
+        self.text.push_str(text);
+        self.cursor = text.len();
+    }
+
+    /// Remove all text.
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+    }
+
+    /// Insert a character at the cursor and move the cursor behind it.
+    pub fn insert(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    /// Insert a string at the cursor and move the cursor behind it.
+    pub fn insert_str(&mut self, s: &str) {
+        self.text.insert_str(self.cursor, s);
+        self.cursor += s.len();
+    }
+
+    /// Move the cursor one character to the left.
+    pub fn move_left(&mut self) {
+        self.cursor = self.previous_boundary();
+    }
+
+    /// Move the cursor one character to the right.
+    pub fn move_right(&mut self) {
+        self.cursor = self.next_boundary();
+    }
+
+    /// Move the cursor to the start of the current or previous word.
+    pub fn move_word_left(&mut self) {
+        self.cursor = self.previous_word_start();
+    }
+
+    /// Move the cursor to the end of the current or next word.
+    pub fn move_word_right(&mut self) {
+        self.cursor = self.next_word_end();
+    }
+
+    /// Move the cursor to the start of the line.
+    pub fn move_home(&mut self) {
+        self.cursor = 0;
+    }
+
+    /// Move the cursor to the end of the line.
+    pub fn move_end(&mut self) {
+        self.cursor = self.text.len();
+    }
+
+    /// Delete the character in front of the cursor.
+    pub fn delete_backward(&mut self) {
+        self.delete_to(self.previous_boundary());
+    }
+
+    /// Delete the character behind the cursor.
+    pub fn delete_forward(&mut self) {
+        self.delete_to(self.next_boundary());
+    }
+
+    /// Delete from the start of the current or previous word up to the cursor.
+    pub fn delete_word_backward(&mut self) {
+        self.delete_to(self.previous_word_start());
+    }
+
+    /// Delete from the cursor up to the end of the current or next word.
+    pub fn delete_word_forward(&mut self) {
+        self.delete_to(self.next_word_end());
+    }
+
+    /// Delete everything in front of the cursor.
+    pub fn delete_to_start(&mut self) {
+        self.delete_to(0);
+    }
+
+    /// Delete everything behind the cursor.
+    pub fn delete_to_end(&mut self) {
+        self.delete_to(self.text.len());
+    }
+
+    /// Delete the text between the cursor and `position`, leaving the cursor where the deleted
+    /// text started.
+    fn delete_to(&mut self, position: usize) {
+        let (start, end) = if position < self.cursor {
+            (position, self.cursor)
+        } else {
+            (self.cursor, position)
+        };
+        self.text.replace_range(start..end, "");
+        self.cursor = start;
+    }
+
+    fn previous_boundary(&self) -> usize {
+        clusters(&self.text[..self.cursor])
+            .last()
+            .map_or(0, |(cluster, _)| self.cursor - cluster.len())
+    }
+
+    fn next_boundary(&self) -> usize {
+        clusters(&self.text[self.cursor..])
+            .next()
+            .map_or(self.cursor, |(cluster, _)| self.cursor + cluster.len())
+    }
+
+    fn previous_word_start(&self) -> usize {
+        self.text[..self.cursor]
+            .trim_end_matches(|c| !is_word_char(c))
+            .trim_end_matches(is_word_char)
+            .len()
+    }
+
+    fn next_word_end(&self) -> usize {
+        let rest = self.text[self.cursor..]
+            .trim_start_matches(|c| !is_word_char(c))
+            .trim_start_matches(is_word_char);
+        self.text.len() - rest.len()
+    }
+}
+
+/// Get whether a character is part of a word. Combining marks belong to the word of the
+/// character they are on.
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || (char_width(c) == 0 && !c.is_control())
+}
+
+/// What happened to the line after a key was handled by [`LineEditor::handle_key`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EditOutcome {
+    /// The line is still being edited.
+    Editing,
+    /// Enter was pressed. This is the line, which has been added to the history.
+    Submit(String),
+    /// Ctrl+C was pressed, and the line has been discarded.
+    Interrupt,
+    /// Ctrl+D was pressed on an empty line to signal the end of the input.
+    Eof,
+}
+
+/// An action that a key press performs.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Action {
+    /// Insert a UTF-16 code unit, which may be half of a surrogate pair.
+    Insert(u16),
+    Left,
+    Right,
+    WordLeft,
+    WordRight,
+    Home,
+    End,
+    Backspace,
+    Delete,
+    DeleteWordBackward,
+    DeleteWordForward,
+    DeleteToStart,
+    DeleteToEnd,
+    HistoryPrevious,
+    HistoryNext,
+    Clear,
+    Submit,
+    Interrupt,
+    EofOrDelete,
+}
+
+impl Action {
+    fn from_key(key: &KeyEventRecord) -> Option<Action> {
+        let state = key.control_key_state;
+        let ctrl = state.has_state(LEFT_CTRL_PRESSED | RIGHT_CTRL_PRESSED);
+        let alt = state.has_state(LEFT_ALT_PRESSED | RIGHT_ALT_PRESSED);
+
+        let action = match key.virtual_key_code {
+            VK_LEFT if ctrl => Action::WordLeft,
+            VK_LEFT => Action::Left,
+            VK_RIGHT if ctrl => Action::WordRight,
+            VK_RIGHT => Action::Right,
+            VK_HOME => Action::Home,
+            VK_END => Action::End,
+            VK_UP => Action::HistoryPrevious,
+            VK_DOWN => Action::HistoryNext,
+            VK_BACK if ctrl || alt => Action::DeleteWordBackward,
+            VK_BACK => Action::Backspace,
+            VK_DELETE if ctrl => Action::DeleteWordForward,
+            VK_DELETE => Action::Delete,
+            VK_RETURN => Action::Submit,
+            VK_ESCAPE => Action::Clear,
+            // Alt without Ctrl, because AltGr is reported as Ctrl+Alt and types characters.
+            _ if alt && !ctrl => match key.u_char {
+                0x62 => Action::WordLeft,          // Alt+B
+                0x66 => Action::WordRight,         // Alt+F
+                0x64 => Action::DeleteWordForward, // Alt+D
+                _ => return None,
+            },
+            _ => match key.u_char {
+                0x01 => Action::Home,               // Ctrl+A
+                0x02 => Action::Left,               // Ctrl+B
+                0x03 => Action::Interrupt,          // Ctrl+C
+                0x04 => Action::EofOrDelete,        // Ctrl+D
+                0x05 => Action::End,                // Ctrl+E
+                0x06 => Action::Right,              // Ctrl+F
+                0x0B => Action::DeleteToEnd,        // Ctrl+K
+                0x15 => Action::DeleteToStart,      // Ctrl+U
+                0x17 => Action::DeleteWordBackward, // Ctrl+W
+                0x00..=0x1F | 0x7F => return None,
+                unit => Action::Insert(unit),
+            },
+        };
+
+        Some(action)
+    }
+}
+
+/// A line editor with history, driven by keyboard input events.
+///
+/// Besides typing, it understands these keys:
+///
+/// | Key | Action |
+/// | --- | --- |
+/// | Left, Right, Ctrl+B, Ctrl+F | Move one character |
+/// | Ctrl+Left, Ctrl+Right, Alt+B, Alt+F | Move one word |
+/// | Home, End, Ctrl+A, Ctrl+E | Move to the start or end of the line |
+/// | Backspace, Delete | Delete one character |
+/// | Ctrl+Backspace, Ctrl+W, Alt+Backspace | Delete the word in front of the cursor |
+/// | Ctrl+Delete, Alt+D | Delete the word behind the cursor |
+/// | Ctrl+U, Ctrl+K | Delete to the start or end of the line |
+/// | Up, Down | Go through the history |
+/// | Escape | Clear the line |
+/// | Enter | Submit the line |
+/// | Ctrl+C | Discard the line |
+/// | Ctrl+D | Signal the end of the input on an empty line, delete one character otherwise |
+///
+/// Going through the history replaces the line with an earlier one; the line that was being
+/// typed comes back when going past the most recent one. Changes to a line from the history
+/// only become part of the history when that line is submitted.
+#[derive(Clone, Debug)]
+pub struct LineEditor {
+    buffer: LineBuffer,
+    history: Vec<String>,
+    history_limit: usize,
+    // The entry of the history that is shown, and the line that was typed before going to it.
+    history_index: Option<usize>,
+    draft: String,
+    high_surrogate: Option<u16>,
+}
+
+impl LineEditor {
+    /// Create a line editor with an empty line and history.
+    pub fn new() -> LineEditor {
+        LineEditor {
+            buffer: LineBuffer::new(),
+            history: Vec::new(),
+            history_limit: DEFAULT_HISTORY_LIMIT,
+            history_index: None,
+            draft: String::new(),
+            high_surrogate: None,
+        }
+    }
+
+    /// Set the number of lines the history holds; older lines are dropped.
+    pub fn history_limit(mut self, history_limit: usize) -> LineEditor {
+        self.history_limit = history_limit;
+        self.truncate_history();
+        self
+    }
+
+    /// Get the line that is being edited.
+    pub fn buffer(&self) -> &LineBuffer {
+        &self.buffer
+    }
+
+    /// Get the line that is being edited mutably, for example to start with some text.
+    pub fn buffer_mut(&mut self) -> &mut LineBuffer {
+        &mut self.buffer
+    }
+
+    /// Get the history, from the oldest to the most recent line.
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// Add a line to the history. Empty lines and repetitions of the most recent line are
+    /// skipped.
+    pub fn add_history(&mut self, line: &str) {
+        if line.is_empty() || self.history.last().map(String::as_str) == Some(line) {
+            return;
+        }
+        self.history.push(line.to_owned());
+        self.truncate_history();
+    }
+
+    /// Handle a keyboard input event.
+    ///
+    /// Key releases and keys that have no action are ignored. The action of a key is repeated
+    /// according to its repeat count. Characters outside of the Basic Multilingual Plane arrive
+    /// as two events with one half of a surrogate pair each, and are inserted once both have
+    /// been handled.
+    pub fn handle_key(&mut self, key: &KeyEventRecord) -> EditOutcome {
+        if !key.key_down {
+            return EditOutcome::Editing;
+        }
+
+        let action = match Action::from_key(key) {
+            Some(action) => action,
+            None => return EditOutcome::Editing,
+        };
+
+        for _ in 0..key.repeat_count.max(1) {
+            let outcome = self.apply(action);
+            if outcome != EditOutcome::Editing {
+                return outcome;
+            }
+        }
+
+        EditOutcome::Editing
+    }
+
+    fn apply(&mut self, action: Action) -> EditOutcome {
+        let high_surrogate = self.high_surrogate.take();
+
+        match action {
+            Action::Insert(unit @ 0xD800..=0xDBFF) => self.high_surrogate = Some(unit),
+            Action::Insert(unit @ 0xDC00..=0xDFFF) => {
+                if let Some(high) = high_surrogate {
+                    if let Some(Ok(c)) =
+                        std::char::decode_utf16([high, unit].iter().copied()).next()
+                    {
+                        self.buffer.insert(c);
+                    }
+                }
+            }
+            Action::Insert(unit) => {
+                if let Some(c) = std::char::from_u32(u32::from(unit)) {
+                    self.buffer.insert(c);
+                }
+            }
+            Action::Left => self.buffer.move_left(),
+            Action::Right => self.buffer.move_right(),
+            Action::WordLeft => self.buffer.move_word_left(),
+            Action::WordRight => self.buffer.move_word_right(),
+            Action::Home => self.buffer.move_home(),
+            Action::End => self.buffer.move_end(),
+            Action::Backspace => self.buffer.delete_backward(),
+            Action::Delete => self.buffer.delete_forward(),
+            Action::DeleteWordBackward => self.buffer.delete_word_backward(),
+            Action::DeleteWordForward => self.buffer.delete_word_forward(),
+            Action::DeleteToStart => self.buffer.delete_to_start(),
+            Action::DeleteToEnd => self.buffer.delete_to_end(),
+            Action::HistoryPrevious => self.history_previous(),
+            Action::HistoryNext => self.history_next(),
+            Action::Clear => self.buffer.clear(),
+            Action::Submit => {
+                let line = self.finish_line();
+                self.add_history(&line);
+                return EditOutcome::Submit(line);
+            }
+            Action::Interrupt => {
+                self.finish_line();
+                return EditOutcome::Interrupt;
+            }
+            Action::EofOrDelete if self.buffer.is_empty() => {
+                self.finish_line();
+                return EditOutcome::Eof;
+            }
+            Action::EofOrDelete => self.buffer.delete_forward(),
+        }
+
+        EditOutcome::Editing
+    }
+
+    fn history_previous(&mut self) {
+        let index = match self.history_index {
+            None if self.history.is_empty() => return,
+            None => {
+                self.draft = self.buffer.text().to_owned();
+                self.history.len() - 1
+            }
+            Some(0) => return,
+            Some(index) => index - 1,
+        };
+
+        self.history_index = Some(index);
+        self.buffer.set_text(&self.history[index]);
+    }
+
+    fn history_next(&mut self) {
+        match self.history_index {
+            None => {}
+            Some(index) if index + 1 < self.history.len() => {
+                self.history_index = Some(index + 1);
+                self.buffer.set_text(&self.history[index + 1]);
+            }
+            Some(_) => {
+                self.history_index = None;
+                self.buffer.set_text(&self.draft);
+            }
+        }
+    }
+
+    /// Take the text of the line and start a new, empty one.
+    fn finish_line(&mut self) -> String {
+        self.history_index = None;
+        self.draft.clear();
+        std::mem::take(&mut self.buffer).text
+    }
+
+    fn truncate_history(&mut self) {
+        if self.history.len() > self.history_limit {
+            let excess = self.history.len() - self.history_limit;
+            self.history.drain(..excess);
+        }
+    }
+}
+
+impl Default for LineEditor {
+    fn default() -> Self {
+        LineEditor::new()
+    }
+}
+
+#[cfg(windows)]
+impl LineEditor {
+    /// Read a line from the console, showing `prompt` in front of it.
+    ///
+    /// The line is drawn at the cursor of the current screen buffer and may wrap over several
+    /// rows. Returns `None` when Ctrl+D is pressed on an empty line, and an error of kind
+    /// [`Interrupted`](io::ErrorKind::Interrupted) when Ctrl+C is pressed. Processed input is
+    /// turned off while reading, so that Ctrl+C arrives as a key event instead of a signal.
+    pub fn read_line(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        let input_handle = Handle::current_in_handle()?;
+        let output_handle = Handle::current_out_handle()?;
+
+        let mode = ConsoleMode::from(input_handle.clone());
+        let original_mode = mode.mode()?;
+        mode.set_mode(original_mode & !ENABLE_PROCESSED_INPUT)?;
+
+        let result = self.read_line_from(
+            &Console::from(input_handle),
+            &Console::from(output_handle.clone()),
+            &ScreenBuffer::new(output_handle),
+            prompt,
+        );
+
+        mode.set_mode(original_mode)?;
+        result
+    }
+
+    fn read_line_from(
+        &mut self,
+        input: &Console,
+        output: &Console,
+        screen_buffer: &ScreenBuffer,
+        prompt: &str,
+    ) -> io::Result<Option<String>> {
+        let mut view = LineView {
+            start: screen_buffer.info()?.cursor_pos(),
+            cells: 0,
+        };
+        view.draw(output, screen_buffer, prompt, &self.buffer)?;
+
+        loop {
+            let key = match input.read_single_input_event()? {
+                InputRecord::KeyEvent(key) => key,
+                _ => continue,
+            };
+
+            let mut line = self.buffer.clone();
+            let outcome = self.handle_key(&key);
+            if outcome == EditOutcome::Editing {
+                view.draw(output, screen_buffer, prompt, &self.buffer)?;
+                continue;
+            }
+
+            // Leave the line as it was, with the cursor on the next row.
+            line.move_end();
+            view.draw(output, screen_buffer, prompt, &line)?;
+            Utf8Writer::new(output.clone()).write_all(b"\r\n")?;
+
+            return match outcome {
+                EditOutcome::Submit(line) => Ok(Some(line)),
+                EditOutcome::Eof => Ok(None),
+                _ => Err(io::Error::new(
+                    io::ErrorKind::Interrupted,
+                    "reading the line was interrupted",
+                )),
+            };
+        }
+    }
+}
+
+/// The place in the screen buffer where a line is drawn.
+#[cfg(windows)]
+struct LineView {
+    /// The position of the first cell of the prompt.
+    start: Coord,
+    /// The number of cells the line took up when it was drawn last.
+    cells: usize,
+}
+
+#[cfg(windows)]
+impl LineView {
+    fn draw(
+        &mut self,
+        output: &Console,
+        screen_buffer: &ScreenBuffer,
+        prompt: &str,
+        buffer: &LineBuffer,
+    ) -> io::Result<()> {
+        let width = screen_buffer.info()?.buffer_size().width;
+        let line = format!("{}{}", prompt, buffer.text());
+
+        output.set_cursor_position(self.start)?;
+        Utf8Writer::new(output.clone()).write_all(line.as_bytes())?;
+
+        // Writing past the last row scrolls the buffer up, and the start of the line with it.
+        let info = screen_buffer.info()?;
+        let end = info.cursor_pos();
+        let unscrolled_end = cursor_after_write(self.start, &line, Size::new(width, i16::MAX));
+        self.start.y = (self.start.y - (unscrolled_end.y - end.y)).max(0);
+
+        // Blank out what is left of a longer line that was drawn before.
+        let cells = ((end.y - self.start.y) as usize * width as usize + end.x as usize)
+            .saturating_sub(self.start.x as usize);
+        if self.cells > cells {
+            output.fill_whit_character(end, (self.cells - cells) as u32, ' ')?;
+        }
+        self.cells = cells;
+
+        let cursor = cursor_after_write(
+            self.start,
+            &line[..prompt.len() + buffer.cursor()],
+            info.buffer_size(),
+        );
+        Ok(output.set_cursor_position(cursor)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        EditOutcome, LineBuffer, LineEditor, LEFT_ALT_PRESSED, LEFT_CTRL_PRESSED, VK_BACK,
+        VK_DELETE, VK_DOWN, VK_END, VK_ESCAPE, VK_HOME, VK_LEFT, VK_RETURN, VK_RIGHT, VK_UP,
+    };
+    use crate::KeyEventRecord;
+
+    /// The virtual-key code of characters typed with an input method.
+    const VK_PACKET: u16 = 0xE7;
+
+    fn key(virtual_key_code: u16, u_char: u16, control_key_state: u32) -> KeyEventRecord {
+        KeyEventRecord {
+            key_down: true,
+            repeat_count: 1,
+            virtual_key_code,
+            virtual_scan_code: 0,
+            u_char,
+            control_key_state: control_key_state.into(),
+        }
+    }
+
+    fn special(virtual_key_code: u16) -> KeyEventRecord {
+        key(virtual_key_code, 0, 0)
+    }
+
+    fn ctrl(letter: char) -> KeyEventRecord {
+        let code = letter.to_ascii_uppercase() as u16;
+        key(code, code - 0x40, LEFT_CTRL_PRESSED)
+    }
+
+    fn type_str(editor: &mut LineEditor, s: &str) {
+        for unit in s.encode_utf16() {
+            assert_eq!(
+                editor.handle_key(&key(VK_PACKET, unit, 0)),
+                EditOutcome::Editing
+            );
+        }
+    }
+
+    fn press(editor: &mut LineEditor, keys: &[KeyEventRecord]) -> EditOutcome {
+        let mut outcome = EditOutcome::Editing;
+        for key in keys {
+            outcome = editor.handle_key(key);
+        }
+        outcome
+    }
+
+    /// Show the cursor in the line as a `|`.
+    fn state(editor: &LineEditor) -> String {
+        let buffer = editor.buffer();
+        let (before, after) = buffer.text().split_at(buffer.cursor());
+        format!("{}|{}", before, after)
+    }
+
+    #[test]
+    fn test_line_buffer_moves_over_combining_marks() {
+        let mut buffer = LineBuffer::with_text("ae\u{0301}\u{4E2D}");
+        buffer.move_left();
+        assert_eq!(buffer.cursor(), 4);
+        buffer.move_left();
+        assert_eq!(buffer.cursor(), 1);
+        buffer.delete_forward();
+        assert_eq!(buffer.text(), "a\u{4E2D}");
+        buffer.move_right();
+        buffer.move_right();
+        assert_eq!(buffer.cursor(), buffer.text().len());
+        buffer.delete_backward();
+        buffer.delete_backward();
+        buffer.delete_backward();
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_line_buffer_words() {
+        let mut buffer = LineBuffer::with_text("cargo  build --re\u{0301}lease");
+        buffer.move_word_left();
+        assert_eq!(&buffer.text()[buffer.cursor()..], "re\u{0301}lease");
+        buffer.move_word_left();
+        assert_eq!(&buffer.text()[buffer.cursor()..], "build --re\u{0301}lease");
+        buffer.move_word_right();
+        assert_eq!(&buffer.text()[buffer.cursor()..], " --re\u{0301}lease");
+
+        buffer.delete_word_backward();
+        assert_eq!(buffer.text(), "cargo   --re\u{0301}lease");
+        buffer.delete_word_forward();
+        assert_eq!(buffer.text(), "cargo  ");
+
+        buffer.move_home();
+        buffer.delete_word_backward();
+        assert_eq!(buffer.text(), "cargo  ");
+        buffer.delete_to_end();
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_typing_and_moving() {
+        let mut editor = LineEditor::new();
+        type_str(&mut editor, "hllo");
+        press(&mut editor, &[special(VK_HOME), special(VK_RIGHT)]);
+        type_str(&mut editor, "e");
+        assert_eq!(state(&editor), "he|llo");
+
+        press(&mut editor, &[special(VK_END), special(VK_LEFT)]);
+        assert_eq!(state(&editor), "hell|o");
+        press(&mut editor, &[ctrl('a'), ctrl('f'), ctrl('e'), ctrl('b')]);
+        assert_eq!(state(&editor), "hell|o");
+        press(&mut editor, &[special(VK_BACK), special(VK_DELETE)]);
+        assert_eq!(state(&editor), "hel|");
+    }
+
+    #[test]
+    fn test_word_keys() {
+        let mut editor = LineEditor::new();
+        type_str(&mut editor, "one two three");
+
+        press(&mut editor, &[key(VK_LEFT, 0, LEFT_CTRL_PRESSED)]);
+        assert_eq!(state(&editor), "one two |three");
+        press(&mut editor, &[key(0x42, 0x62, LEFT_ALT_PRESSED)]); // Alt+B
+        assert_eq!(state(&editor), "one |two three");
+        press(&mut editor, &[key(VK_RIGHT, 0, LEFT_CTRL_PRESSED)]);
+        assert_eq!(state(&editor), "one two| three");
+        press(&mut editor, &[ctrl('w')]);
+        assert_eq!(state(&editor), "one | three");
+        press(&mut editor, &[key(VK_DELETE, 0, LEFT_CTRL_PRESSED)]);
+        assert_eq!(state(&editor), "one |");
+        press(&mut editor, &[key(VK_BACK, 0x7F, LEFT_CTRL_PRESSED)]);
+        assert_eq!(state(&editor), "|");
+    }
+
+    #[test]
+    fn test_delete_to_start_and_end() {
+        let mut editor = LineEditor::new();
+        type_str(&mut editor, "abcdef");
+        press(
+            &mut editor,
+            &[special(VK_LEFT), special(VK_LEFT), ctrl('k')],
+        );
+        assert_eq!(state(&editor), "abcd|");
+        press(&mut editor, &[special(VK_LEFT), ctrl('u')]);
+        assert_eq!(state(&editor), "|d");
+    }
+
+    #[test]
+    fn test_ignores_releases_and_repeats_keys() {
+        let mut editor = LineEditor::new();
+        let mut release = key(0x41, 0x61, 0);
+        release.key_down = false;
+        editor.handle_key(&release);
+        assert_eq!(state(&editor), "|");
+
+        let mut repeated = key(0x41, 0x61, 0);
+        repeated.repeat_count = 3;
+        editor.handle_key(&repeated);
+        assert_eq!(state(&editor), "aaa|");
+
+        // AltGr is Ctrl+Alt and types characters.
+        editor.handle_key(&key(0x51, 0x40, LEFT_CTRL_PRESSED | LEFT_ALT_PRESSED));
+        assert_eq!(state(&editor), "aaa@|");
+    }
+
+    #[test]
+    fn test_surrogate_pairs() {
+        let mut editor = LineEditor::new();
+        type_str(&mut editor, "a\u{1F600}b");
+        assert_eq!(editor.buffer().text(), "a\u{1F600}b");
+
+        // A lone low surrogate is dropped, and so is a high surrogate that isn't followed by one.
+        press(&mut editor, &[key(VK_PACKET, 0xDE00, 0)]);
+        press(&mut editor, &[key(VK_PACKET, 0xD83D, 0), special(VK_LEFT)]);
+        type_str(&mut editor, "c");
+        assert_eq!(state(&editor), "a\u{1F600}c|b");
+    }
+
+    #[test]
+    fn test_submit_interrupt_and_eof() {
+        let mut editor = LineEditor::new();
+        type_str(&mut editor, "first");
+        assert_eq!(
+            press(&mut editor, &[key(VK_RETURN, 0x0D, 0)]),
+            EditOutcome::Submit("first".to_owned())
+        );
+        assert_eq!(state(&editor), "|");
+
+        type_str(&mut editor, "second");
+        assert_eq!(press(&mut editor, &[ctrl('c')]), EditOutcome::Interrupt);
+        assert_eq!(state(&editor), "|");
+
+        type_str(&mut editor, "ab");
+        press(&mut editor, &[special(VK_HOME), ctrl('d')]);
+        assert_eq!(state(&editor), "|b");
+        press(&mut editor, &[key(VK_ESCAPE, 0x1B, 0)]);
+        assert_eq!(press(&mut editor, &[ctrl('d')]), EditOutcome::Eof);
+
+        assert_eq!(editor.history(), ["first"]);
+    }
+
+    #[test]
+    fn test_history() {
+        let mut editor = LineEditor::new().history_limit(3);
+        for line in &["one", "two", "two", "", "three", "four"] {
+            type_str(&mut editor, line);
+            press(&mut editor, &[key(VK_RETURN, 0x0D, 0)]);
+        }
+        assert_eq!(editor.history(), ["two", "three", "four"]);
+
+        type_str(&mut editor, "draft");
+        press(&mut editor, &[special(VK_UP)]);
+        assert_eq!(state(&editor), "four|");
+        press(
+            &mut editor,
+            &[special(VK_UP), special(VK_UP), special(VK_UP)],
+        );
+        assert_eq!(state(&editor), "two|");
+        press(&mut editor, &[special(VK_DOWN)]);
+        assert_eq!(state(&editor), "three|");
+        press(&mut editor, &[special(VK_DOWN), special(VK_DOWN)]);
+        assert_eq!(state(&editor), "draft|");
+        press(&mut editor, &[special(VK_DOWN)]);
+        assert_eq!(state(&editor), "draft|");
+
+        // Editing a line from the history doesn't change the history.
+        press(&mut editor, &[special(VK_UP)]);
+        type_str(&mut editor, "!");
+        assert_eq!(
+            press(&mut editor, &[key(VK_RETURN, 0x0D, 0)]),
+            EditOutcome::Submit("four!".to_owned())
+        );
+        assert_eq!(editor.history(), ["three", "four", "four!"]);
+    }
+}
@@ -0,0 +1,198 @@
+use std::io;
+use std::marker::PhantomData;
+use std::os::windows::io::{AsHandle, BorrowedHandle};
+use std::ptr;
+use std::time::Duration;
+
+use winapi::shared::minwindef::FALSE;
+use winapi::um::synchapi::{CreateMutexW, OpenMutexW, ReleaseMutex};
+use winapi::um::winnt::SYNCHRONIZE;
+
+use crate::wait::wait_one;
+use crate::win32::ERROR_ALREADY_EXISTS;
+use crate::{nonnull_handle_result, wide_nul, Error, Handle, Result, WaitResult, Waitable};
+
+/// A [Windows mutex](https://docs.microsoft.com/en-us/windows/win32/sync/mutex-objects).
+///
+/// Unlike [`std::sync::Mutex`], this does not hold any data, but a named mutex can be shared
+/// between processes, for example to make sure only a single instance of a program runs with
+/// [`Mutex::single_instance`]. A mutex is locked by a thread, and only that thread can unlock it
+/// again.
+///
+/// Clones refer to the same mutex.
+#[derive(Clone, Debug)]
+pub struct Mutex(Handle);
+
+impl Mutex {
+    /// Construct a new mutex that is not locked.
+    ///
+    /// This wraps
+    /// [`CreateMutexW`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-createmutexw).
+    pub fn new() -> Result<Mutex> {
+        Ok(Mutex::create(None)?.0)
+    }
+
+    /// Create a named mutex, unless a mutex with that name exists already, in which case `None`
+    /// is returned.
+    ///
+    /// As long as the returned mutex is kept alive, other processes calling this with the same
+    /// name get `None`. Names starting with `Global\` are shared between all sessions of the
+    /// machine, other names only within the current session.
+    ///
+    /// This wraps
+    /// [`CreateMutexW`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-createmutexw).
+    pub fn single_instance(name: &str) -> Result<Option<Mutex>> {
+        let (mutex, existed) = Mutex::create(Some(name))?;
+        Ok(if existed { None } else { Some(mutex) })
+    }
+
+    /// Open an existing named mutex.
+    ///
+    /// This wraps
+    /// [`OpenMutexW`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-openmutexw).
+    pub fn open(name: &str) -> Result<Mutex> {
+        let error = |e: Error| e.with_argument("name", &name);
+        let wide_name = wide_nul("OpenMutexW", name).map_err(error)?;
+        let handle = nonnull_handle_result("OpenMutexW", unsafe {
+            OpenMutexW(SYNCHRONIZE, FALSE, wide_name.as_ptr())
+        })
+        .map_err(error)?;
+
+        let handle = unsafe { Handle::from_raw(handle) };
+        Ok(Mutex(handle))
+    }
+
+    /// Create a mutex, returning whether a mutex with the name existed already.
+    fn create(name: Option<&str>) -> Result<(Mutex, bool)> {
+        let error = |e: Error| e.with_argument("name", &name);
+        let wide_name = name
+            .map(|name| wide_nul("CreateMutexW", name))
+            .transpose()
+            .map_err(error)?;
+        let handle = nonnull_handle_result("CreateMutexW", unsafe {
+            CreateMutexW(
+                ptr::null_mut(),
+                FALSE,
+                wide_name.as_ref().map_or(ptr::null(), |name| name.as_ptr()),
+            )
+        })
+        .map_err(error)?;
+        // This has to be read right away, before any other call changes the last error.
+        let existed =
+            io::Error::last_os_error().raw_os_error() == Some(ERROR_ALREADY_EXISTS as i32);
+
+        let handle = unsafe { Handle::from_raw(handle) };
+        Ok((Mutex(handle), existed))
+    }
+
+    /// Wait until the mutex is unlocked, and lock it.
+    ///
+    /// The current thread may lock the mutex more than once; it is unlocked when every guard
+    /// has been dropped.
+    pub fn lock(&self) -> Result<MutexGuard<'_>> {
+        let result = wait_one(&self.0, None)?;
+        Ok(self.guard(result))
+    }
+
+    /// Lock the mutex if it is unlocked, without waiting.
+    pub fn try_lock(&self) -> Result<Option<MutexGuard<'_>>> {
+        self.lock_timeout(Duration::from_secs(0))
+    }
+
+    /// Wait at most `timeout` until the mutex is unlocked, and lock it.
+    pub fn lock_timeout(&self, timeout: Duration) -> Result<Option<MutexGuard<'_>>> {
+        Ok(match wait_one(&self.0, Some(timeout))? {
+            WaitResult::TimedOut => None,
+            result => Some(self.guard(result)),
+        })
+    }
+
+    fn guard(&self, result: WaitResult) -> MutexGuard<'_> {
+        MutexGuard {
+            mutex: self,
+            abandoned: matches!(result, WaitResult::Abandoned(_)),
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Access the underlying handle to the mutex.
+    pub fn handle(&self) -> &Handle {
+        &self.0
+    }
+}
+
+impl AsHandle for Mutex {
+    fn as_handle(&self) -> BorrowedHandle<'_> {
+        self.0.as_handle()
+    }
+}
+
+impl Waitable for Mutex {
+    fn handle(&self) -> &Handle {
+        &self.0
+    }
+}
+
+/// A lock on a [`Mutex`], which is unlocked when the guard is dropped.
+///
+/// The guard can't be sent to another thread, because only the thread that locked a mutex can
+/// unlock it.
+#[derive(Debug)]
+pub struct MutexGuard<'a> {
+    mutex: &'a Mutex,
+    abandoned: bool,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl MutexGuard<'_> {
+    /// Get whether the thread that had locked the mutex before exited without unlocking it.
+    ///
+    /// Whatever the mutex protects may have been left in an inconsistent state.
+    pub fn abandoned(&self) -> bool {
+        self.abandoned
+    }
+}
+
+impl Drop for MutexGuard<'_> {
+    /// This wraps
+    /// [`ReleaseMutex`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-releasemutex).
+    fn drop(&mut self) {
+        // This can only fail if the current thread doesn't own the mutex, which the guard rules
+        // out.
+        unsafe { ReleaseMutex(*self.mutex.0) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::Mutex;
+
+    #[test]
+    fn test_lock() {
+        let mutex = Mutex::new().unwrap();
+        let guard = mutex.lock().unwrap();
+        assert!(!guard.abandoned());
+
+        let other = mutex.clone();
+        let locked_elsewhere = thread::spawn(move || other.try_lock().unwrap().is_some());
+        assert!(!locked_elsewhere.join().unwrap());
+
+        drop(guard);
+        let other = mutex.clone();
+        let locked_elsewhere = thread::spawn(move || other.try_lock().unwrap().is_some());
+        assert!(locked_elsewhere.join().unwrap());
+    }
+
+    #[test]
+    fn test_single_instance() {
+        let name = format!("crossterm_winapi_test_{}", std::process::id());
+        let first = Mutex::single_instance(&name).unwrap();
+        assert!(first.is_some());
+        assert!(Mutex::single_instance(&name).unwrap().is_none());
+
+        drop(first);
+        assert!(Mutex::single_instance(&name).unwrap().is_some());
+    }
+}
@@ -0,0 +1,352 @@
+//! This module contains [`PixelMapping`], which maps between the cells of a screen buffer and the
+//! pixels of the client area of the console window, for placing images and for mouse positions
+//! finer than a cell.
+//!
+//! The client area shows the cells of the window of the screen buffer, starting with its
+//! upper-left cell, so scrolling the window moves every cell to other pixels. Pixels are
+//! relative to the upper-left corner of the client area, and may lie outside of it.
+
+use std::convert::TryFrom;
+use std::fmt;
+
+use super::{ConversionError, Coord, Size, WindowPositions};
+
+/// A position in pixels, relative to the upper-left corner of the client area of the console
+/// window.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Pixel {
+    /// The number of pixels to the right.
+    pub x: i32,
+    /// The number of pixels down.
+    pub y: i32,
+}
+
+impl Pixel {
+    /// Create a pixel position.
+    pub fn new(x: i32, y: i32) -> Pixel {
+        Pixel { x, y }
+    }
+}
+
+impl fmt::Display for Pixel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}px, {}px)", self.x, self.y)
+    }
+}
+
+/// A mapping between cells of a screen buffer and pixels of the client area of the console
+/// window.
+///
+/// It is built from the cell size of the font, like [`FontInfo::size`](crate::FontInfo::size),
+/// the window of the screen buffer, like [`ScreenBufferInfo::terminal_window`], and the pixel
+/// at which the upper-left cell of the window starts, which is `(0, 0)` unless the terminal draws
+/// a margin around the cells.
+///
+/// [`ScreenBufferInfo::terminal_window`]: crate::ScreenBufferInfo::terminal_window
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PixelMapping {
+    cell_size: Size,
+    window: WindowPositions,
+    origin: Pixel,
+}
+
+impl PixelMapping {
+    /// Create a mapping for cells of `cell_size` pixels in `window`, whose upper-left cell starts
+    /// at the pixel `origin`.
+    ///
+    /// Returns `None` if the cell size is less than one pixel in either dimension.
+    pub fn new(cell_size: Size, window: WindowPositions, origin: Pixel) -> Option<PixelMapping> {
+        if cell_size.width < 1 || cell_size.height < 1 {
+            return None;
+        }
+        Some(PixelMapping {
+            cell_size,
+            window,
+            origin,
+        })
+    }
+
+    /// Get the size of a cell in pixels.
+    pub fn cell_size(&self) -> Size {
+        self.cell_size
+    }
+
+    /// Get the window of the screen buffer that is shown in the client area.
+    pub fn window(&self) -> WindowPositions {
+        self.window
+    }
+
+    /// Get the pixel at which the upper-left cell of the window starts.
+    pub fn origin(&self) -> Pixel {
+        self.origin
+    }
+
+    /// Whether the cell is shown in the client area, because it lies in the window.
+    pub fn is_visible(&self, cell: Coord) -> bool {
+        self.window.contains(cell)
+    }
+
+    /// Get the upper-left pixel of the cell in the screen buffer.
+    ///
+    /// Cells outside of the window map to pixels outside of the client area, so that the position
+    /// of an image that starts above the window is kept.
+    ///
+    /// Fails with [`ConversionError::Overflow`] if the pixel lies outside of the range of a
+    /// [`Pixel`], which can only happen for an origin far from the client area.
+    pub fn cell_to_pixel(&self, cell: Coord) -> Result<Pixel, ConversionError> {
+        let pixel = |cell: i16, first_cell: i16, cell_size: i16, origin: i32| {
+            (i32::from(cell) - i32::from(first_cell))
+                .checked_mul(i32::from(cell_size))
+                .and_then(|distance| origin.checked_add(distance))
+                .ok_or(ConversionError::Overflow)
+        };
+        Ok(Pixel {
+            x: pixel(
+                cell.x,
+                self.window.left,
+                self.cell_size.width,
+                self.origin.x,
+            )?,
+            y: pixel(
+                cell.y,
+                self.window.top,
+                self.cell_size.height,
+                self.origin.y,
+            )?,
+        })
+    }
+
+    /// Get the cell in the screen buffer that contains the pixel.
+    ///
+    /// Every pixel of a cell maps to it, so pixels at the right and bottom edge of the client area
+    /// map to the cell that is only partially shown there.
+    ///
+    /// Fails with [`ConversionError::Overflow`] if the cell lies outside of the range of a
+    /// [`Coord`].
+    pub fn pixel_to_cell(&self, pixel: Pixel) -> Result<Coord, ConversionError> {
+        self.pixel_to_cell_offset(pixel).map(|(cell, _)| cell)
+    }
+
+    /// Get the cell in the screen buffer that contains the pixel, and the offset of the pixel
+    /// from the upper-left pixel of that cell, which lies within the cell size.
+    ///
+    /// Fails with [`ConversionError::Overflow`] if the cell lies outside of the range of a
+    /// [`Coord`].
+    pub fn pixel_to_cell_offset(&self, pixel: Pixel) -> Result<(Coord, Pixel), ConversionError> {
+        let distance =
+            |pixel: i32, origin: i32| pixel.checked_sub(origin).ok_or(ConversionError::Overflow);
+        let (x, offset_x) = split(
+            distance(pixel.x, self.origin.x)?,
+            self.cell_size.width,
+            self.window.left,
+        )?;
+        let (y, offset_y) = split(
+            distance(pixel.y, self.origin.y)?,
+            self.cell_size.height,
+            self.window.top,
+        )?;
+        Ok((Coord::new(x, y), Pixel::new(offset_x, offset_y)))
+    }
+
+    /// Get the size in cells of the part of the window that is at least partially shown in a
+    /// client area of `client_size` pixels.
+    pub fn visible_size(&self, client_size: Pixel) -> Size {
+        let cells = |pixels: i32, cell: i16| {
+            let cell = i32::from(cell);
+            let pixels = pixels.max(0);
+            // Round up, without overflowing for the largest sizes.
+            let cells = pixels / cell + i32::from(pixels % cell != 0);
+            i16::try_from(cells).unwrap_or(i16::MAX)
+        };
+        Size::new(
+            cells(
+                client_size.x.saturating_sub(self.origin.x),
+                self.cell_size.width,
+            )
+            .min(self.window.width()),
+            cells(
+                client_size.y.saturating_sub(self.origin.y),
+                self.cell_size.height,
+            )
+            .min(self.window.height()),
+        )
+    }
+}
+
+/// Split a distance in pixels from the start of the window into the cell it lies in, counted from
+/// `first_cell`, and the offset within that cell.
+fn split(pixels: i32, cell_size: i16, first_cell: i16) -> Result<(i16, i32), ConversionError> {
+    let cell_size = i32::from(cell_size);
+    let cell = i32::from(first_cell) + pixels.div_euclid(cell_size);
+    let cell = i16::try_from(cell).map_err(|_| ConversionError::Overflow)?;
+    Ok((cell, pixels.rem_euclid(cell_size)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pixel, PixelMapping};
+    use crate::{ConversionError, Coord, Size, WindowPositions};
+
+    fn window(left: i16, top: i16, width: i16, height: i16) -> WindowPositions {
+        WindowPositions::from_origin_size(Coord::new(left, top), Size::new(width, height))
+    }
+
+    #[test]
+    fn test_new() {
+        let window = window(0, 0, 80, 25);
+        assert!(PixelMapping::new(Size::new(8, 16), window, Pixel::default()).is_some());
+        assert!(PixelMapping::new(Size::new(0, 16), window, Pixel::default()).is_none());
+        assert!(PixelMapping::new(Size::new(8, -1), window, Pixel::default()).is_none());
+    }
+
+    #[test]
+    fn test_cell_to_pixel() {
+        let mapping =
+            PixelMapping::new(Size::new(8, 16), window(0, 0, 80, 25), Pixel::default()).unwrap();
+        assert_eq!(
+            mapping.cell_to_pixel(Coord::new(0, 0)).unwrap(),
+            Pixel::new(0, 0)
+        );
+        assert_eq!(
+            mapping.cell_to_pixel(Coord::new(10, 2)).unwrap(),
+            Pixel::new(80, 32)
+        );
+
+        // A scrolled window shows later rows and columns at the same pixels.
+        let mapping =
+            PixelMapping::new(Size::new(8, 16), window(4, 100, 80, 25), Pixel::new(2, 3)).unwrap();
+        assert_eq!(
+            mapping.cell_to_pixel(Coord::new(4, 100)).unwrap(),
+            Pixel::new(2, 3)
+        );
+        assert_eq!(
+            mapping.cell_to_pixel(Coord::new(14, 102)).unwrap(),
+            Pixel::new(82, 35)
+        );
+        // Rows above the window lie above the client area.
+        assert_eq!(
+            mapping.cell_to_pixel(Coord::new(4, 98)).unwrap(),
+            Pixel::new(2, -29)
+        );
+        assert!(!mapping.is_visible(Coord::new(4, 98)));
+    }
+
+    #[test]
+    fn test_pixel_to_cell() {
+        let mapping =
+            PixelMapping::new(Size::new(8, 16), window(4, 100, 80, 25), Pixel::new(2, 3)).unwrap();
+        assert_eq!(
+            mapping.pixel_to_cell_offset(Pixel::new(2, 3)).unwrap(),
+            (Coord::new(4, 100), Pixel::new(0, 0))
+        );
+        assert_eq!(
+            mapping.pixel_to_cell_offset(Pixel::new(85, 50)).unwrap(),
+            (Coord::new(14, 102), Pixel::new(3, 15))
+        );
+        // Pixels in the margin belong to the cells before the window.
+        assert_eq!(
+            mapping.pixel_to_cell_offset(Pixel::new(0, 0)).unwrap(),
+            (Coord::new(3, 99), Pixel::new(6, 13))
+        );
+
+        let mapping =
+            PixelMapping::new(Size::new(8, 16), window(0, 0, 80, 25), Pixel::default()).unwrap();
+        assert_eq!(
+            mapping.pixel_to_cell(Pixel::new(i32::MAX, 0)),
+            Err(ConversionError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_extreme_pixels() {
+        let mapping =
+            PixelMapping::new(Size::new(1, 1), window(0, 0, 80, 25), Pixel::new(-1, 1)).unwrap();
+        // The distance from the origin doesn't fit in an `i32`.
+        assert_eq!(
+            mapping.pixel_to_cell(Pixel::new(i32::MAX, 0)),
+            Err(ConversionError::Overflow)
+        );
+        assert_eq!(
+            mapping.pixel_to_cell(Pixel::new(0, i32::MIN)),
+            Err(ConversionError::Overflow)
+        );
+
+        assert_eq!(
+            mapping.visible_size(Pixel::new(i32::MAX, i32::MIN)),
+            Size::new(80, 0)
+        );
+
+        // An origin at the edge of the range of pixels leaves no room for the cells after it.
+        let mapping = PixelMapping::new(
+            Size::new(8, 16),
+            window(0, 0, 80, 25),
+            Pixel::new(i32::MAX, 0),
+        )
+        .unwrap();
+        assert_eq!(
+            mapping.cell_to_pixel(Coord::new(0, 24)),
+            Ok(Pixel::new(i32::MAX, 384))
+        );
+        assert_eq!(
+            mapping.cell_to_pixel(Coord::new(1, 0)),
+            Err(ConversionError::Overflow)
+        );
+        let mapping = PixelMapping::new(
+            Size::new(1, 1),
+            window(0, 0, i16::MAX, i16::MAX),
+            Pixel::new(i32::MIN, 0),
+        )
+        .unwrap();
+        assert_eq!(
+            mapping.visible_size(Pixel::new(i32::MAX, i32::MAX)),
+            Size::new(i16::MAX, i16::MAX)
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for &(window, origin) in &[
+            (window(0, 0, 80, 25), Pixel::default()),
+            (window(17, 9000, 120, 30), Pixel::new(4, 4)),
+        ] {
+            let mapping = PixelMapping::new(Size::new(9, 19), window, origin).unwrap();
+            for cell in window.cells().step_by(7) {
+                let pixel = mapping.cell_to_pixel(cell).unwrap();
+                assert_eq!(mapping.pixel_to_cell(pixel).unwrap(), cell);
+
+                // Every pixel of a cell maps back to it.
+                let last = Pixel::new(pixel.x + 8, pixel.y + 18);
+                assert_eq!(
+                    mapping.pixel_to_cell_offset(last).unwrap(),
+                    (cell, Pixel::new(8, 18))
+                );
+                let next = Pixel::new(pixel.x + 9, pixel.y + 19);
+                assert_eq!(
+                    mapping.pixel_to_cell(next).unwrap(),
+                    Coord::new(cell.x + 1, cell.y + 1)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_visible_size() {
+        let mapping =
+            PixelMapping::new(Size::new(8, 16), window(0, 50, 80, 25), Pixel::new(2, 2)).unwrap();
+        // Partially shown cells count.
+        assert_eq!(
+            mapping.visible_size(Pixel::new(643, 402)),
+            Size::new(80, 25)
+        );
+        assert_eq!(
+            mapping.visible_size(Pixel::new(322, 201)),
+            Size::new(40, 13)
+        );
+        // The window limits the size.
+        assert_eq!(
+            mapping.visible_size(Pixel::new(4000, 4000)),
+            Size::new(80, 25)
+        );
+        assert_eq!(mapping.visible_size(Pixel::new(0, 0)), Size::new(0, 0));
+    }
+}
@@ -0,0 +1,244 @@
+//! This module contains [`ProcessConsole`], which allocates, attaches and frees the console of the
+//! current process.
+//!
+//! A process has at most one console. Console applications get one when they start, but GUI
+//! applications and services don't, and have to allocate a new console or attach to the console
+//! of another process first. Handles opened afterwards, like
+//! [`Handle::current_out_handle`](crate::Handle::current_out_handle), refer to that console.
+
+use std::error;
+use std::fmt;
+use std::io;
+
+#[cfg(windows)]
+use winapi::shared::windef::HWND;
+#[cfg(windows)]
+use winapi::um::{
+    consoleapi::AllocConsole,
+    wincon::{AttachConsole, FreeConsole, GetConsoleWindow, ATTACH_PARENT_PROCESS},
+};
+
+#[cfg(windows)]
+use crate::handle::report_close_error;
+#[cfg(windows)]
+use crate::result;
+use crate::win32::{ERROR_ACCESS_DENIED, ERROR_INVALID_HANDLE};
+use crate::Error;
+
+/// An error of allocating, attaching or freeing the console of the process.
+#[derive(Debug)]
+pub enum ProcessConsoleError {
+    /// The process is already attached to a console, and has to free it first.
+    AlreadyAttached(Error),
+    /// The process to attach to, like the parent process, doesn't have a console.
+    NoConsole(Error),
+    /// A call to WinAPI failed.
+    Os(Error),
+}
+
+impl fmt::Display for ProcessConsoleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProcessConsoleError::AlreadyAttached(_) => {
+                f.write_str("the process is already attached to a console")
+            }
+            ProcessConsoleError::NoConsole(_) => {
+                f.write_str("the process to attach to doesn't have a console")
+            }
+            ProcessConsoleError::Os(error) => error.fmt(f),
+        }
+    }
+}
+
+impl error::Error for ProcessConsoleError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ProcessConsoleError::AlreadyAttached(error)
+            | ProcessConsoleError::NoConsole(error)
+            | ProcessConsoleError::Os(error) => Some(error),
+        }
+    }
+}
+
+impl From<Error> for ProcessConsoleError {
+    /// Convert an error of a call to WinAPI, recognizing `ERROR_ACCESS_DENIED` of `AllocConsole`
+    /// and `AttachConsole` as [`AlreadyAttached`](ProcessConsoleError::AlreadyAttached), and
+    /// `ERROR_INVALID_HANDLE` of `AttachConsole` as [`NoConsole`](ProcessConsoleError::NoConsole).
+    fn from(error: Error) -> Self {
+        match (error.api(), error.code()) {
+            ("AllocConsole", Some(ERROR_ACCESS_DENIED))
+            | ("AttachConsole", Some(ERROR_ACCESS_DENIED)) => {
+                ProcessConsoleError::AlreadyAttached(error)
+            }
+            ("AttachConsole", Some(ERROR_INVALID_HANDLE)) => ProcessConsoleError::NoConsole(error),
+            _ => ProcessConsoleError::Os(error),
+        }
+    }
+}
+
+impl From<ProcessConsoleError> for Error {
+    /// Get back the error of the call to WinAPI.
+    fn from(error: ProcessConsoleError) -> Self {
+        match error {
+            ProcessConsoleError::AlreadyAttached(error)
+            | ProcessConsoleError::NoConsole(error)
+            | ProcessConsoleError::Os(error) => error,
+        }
+    }
+}
+
+impl From<ProcessConsoleError> for io::Error {
+    fn from(error: ProcessConsoleError) -> Self {
+        Error::from(error).into()
+    }
+}
+
+/// A console allocated for the current process, which is freed when this is dropped.
+///
+/// Only [`alloc`](ProcessConsole::alloc) returns this guard. Attaching to the console of another
+/// process with [`attach`](ProcessConsole::attach) or
+/// [`attach_parent`](ProcessConsole::attach_parent) stays in effect until
+/// [`detach`](ProcessConsole::detach) is called, because that console outlives the process.
+///
+/// Errors of freeing the console while it is dropped are passed to the hook set with
+/// [`set_close_error_hook`](crate::set_close_error_hook). Use [`free`](ProcessConsole::free) to
+/// handle them instead, or [`keep`](ProcessConsole::keep) to stay attached.
+#[cfg(windows)]
+#[derive(Debug)]
+#[must_use = "the console is freed when the `ProcessConsole` is dropped"]
+pub struct ProcessConsole {
+    // Only created by `alloc`.
+    _private: (),
+}
+
+#[cfg(windows)]
+impl ProcessConsole {
+    /// Allocate a new console for the process.
+    ///
+    /// The standard handles of the process are set to the input and output of the new console.
+    ///
+    /// This wraps
+    /// [`AllocConsole`](https://docs.microsoft.com/en-us/windows/console/allocconsole).
+    pub fn alloc() -> Result<ProcessConsole, ProcessConsoleError> {
+        result("AllocConsole", unsafe { AllocConsole() })?;
+        Ok(ProcessConsole { _private: () })
+    }
+
+    /// Attach the process to the console of the process with the id `process_id`, until
+    /// [`detach`](ProcessConsole::detach) is called.
+    ///
+    /// This wraps
+    /// [`AttachConsole`](https://docs.microsoft.com/en-us/windows/console/attachconsole).
+    pub fn attach(process_id: u32) -> Result<(), ProcessConsoleError> {
+        result("AttachConsole", unsafe { AttachConsole(process_id) })
+            .map_err(|e| e.with_argument("process_id", &process_id))?;
+        Ok(())
+    }
+
+    /// Attach the process to the console of its parent process, like the shell it was started
+    /// from, until [`detach`](ProcessConsole::detach) is called.
+    ///
+    /// This wraps
+    /// [`AttachConsole`](https://docs.microsoft.com/en-us/windows/console/attachconsole) called
+    /// with `ATTACH_PARENT_PROCESS`.
+    pub fn attach_parent() -> Result<(), ProcessConsoleError> {
+        result("AttachConsole", unsafe {
+            AttachConsole(ATTACH_PARENT_PROCESS)
+        })?;
+        Ok(())
+    }
+
+    /// Free the console, returning the error if that fails.
+    ///
+    /// This wraps
+    /// [`FreeConsole`](https://docs.microsoft.com/en-us/windows/console/freeconsole).
+    pub fn free(self) -> Result<(), ProcessConsoleError> {
+        std::mem::forget(self);
+        Self::detach()
+    }
+
+    /// Stay attached to the console instead of freeing it when this is dropped.
+    pub fn keep(self) {
+        std::mem::forget(self);
+    }
+
+    /// Detach the process from its console, whether it was allocated, attached or the process
+    /// started with it.
+    ///
+    /// This wraps
+    /// [`FreeConsole`](https://docs.microsoft.com/en-us/windows/console/freeconsole).
+    pub fn detach() -> Result<(), ProcessConsoleError> {
+        result("FreeConsole", unsafe { FreeConsole() })?;
+        Ok(())
+    }
+
+    /// Get the window of the console of the process, or `None` if there is no console or it
+    /// has no window, like a pseudoconsole.
+    ///
+    /// This wraps
+    /// [`GetConsoleWindow`](https://docs.microsoft.com/en-us/windows/console/getconsolewindow).
+    pub fn window() -> Option<HWND> {
+        let window = unsafe { GetConsoleWindow() };
+        if window.is_null() {
+            None
+        } else {
+            Some(window)
+        }
+    }
+}
+
+#[cfg(windows)]
+impl Drop for ProcessConsole {
+    fn drop(&mut self) {
+        if let Err(error) = result("FreeConsole", unsafe { FreeConsole() }) {
+            report_close_error(&error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error;
+
+    use super::{ProcessConsoleError, ERROR_ACCESS_DENIED, ERROR_INVALID_HANDLE};
+    use crate::{Error, ErrorKind};
+
+    #[test]
+    fn test_from_error() {
+        for &api in &["AllocConsole", "AttachConsole"] {
+            assert!(matches!(
+                ProcessConsoleError::from(Error::from_code(api, ERROR_ACCESS_DENIED)),
+                ProcessConsoleError::AlreadyAttached(_)
+            ));
+        }
+        assert!(matches!(
+            ProcessConsoleError::from(Error::from_code("AttachConsole", ERROR_INVALID_HANDLE)),
+            ProcessConsoleError::NoConsole(_)
+        ));
+
+        // The same codes mean something else for other functions.
+        let error =
+            ProcessConsoleError::from(Error::from_code("FreeConsole", ERROR_INVALID_HANDLE));
+        assert!(matches!(error, ProcessConsoleError::Os(_)));
+        assert_eq!(Error::from(error).code(), Some(ERROR_INVALID_HANDLE));
+    }
+
+    #[test]
+    fn test_into_error() {
+        // The conversion gives back the original error, with its code.
+        let error =
+            ProcessConsoleError::from(Error::from_code("AllocConsole", ERROR_ACCESS_DENIED));
+        assert!(error::Error::source(&error).is_some());
+        let error = Error::from(error);
+        assert_eq!(error.api(), "AllocConsole");
+        assert_eq!(error.code(), Some(ERROR_ACCESS_DENIED));
+        assert_eq!(error.kind(), ErrorKind::PermissionDenied);
+
+        let error = Error::from(ProcessConsoleError::from(Error::from_code(
+            "AttachConsole",
+            ERROR_INVALID_HANDLE,
+        )));
+        assert_eq!(error.api(), "AttachConsole");
+        assert_eq!(error.code(), Some(ERROR_INVALID_HANDLE));
+    }
+}
@@ -0,0 +1,386 @@
+//! This module contains [`PseudoConsole`], a console without a window whose input and output go
+//! through pipes, for hosting child processes in a terminal of your own.
+//!
+//! Child processes are attached to a pseudoconsole with an attribute list in their
+//! `STARTUPINFOEX`. Setting up that list and checking sizes is plain logic, which is kept apart
+//! from the WinAPI calls so it can be tested anywhere.
+
+#[cfg(any(windows, test))]
+use std::mem::size_of;
+#[cfg(windows)]
+use std::ptr::{null, null_mut};
+
+#[cfg(windows)]
+use winapi::ctypes::c_void;
+#[cfg(windows)]
+use winapi::shared::minwindef::FALSE;
+#[cfg(windows)]
+use winapi::um::{
+    consoleapi::{ClosePseudoConsole, CreatePseudoConsole, ResizePseudoConsole},
+    namedpipeapi::CreatePipe,
+    processthreadsapi::{
+        CreateProcessW, DeleteProcThreadAttributeList, InitializeProcThreadAttributeList,
+        UpdateProcThreadAttribute, LPPROC_THREAD_ATTRIBUTE_LIST, PROCESS_INFORMATION,
+    },
+    winbase::{EXTENDED_STARTUPINFO_PRESENT, STARTUPINFOEXW},
+    wincontypes::HPCON,
+};
+
+#[cfg(any(windows, test))]
+use crate::win32::{
+    ERROR_INSUFFICIENT_BUFFER, FACILITY_WIN32, PROC_THREAD_ATTRIBUTE_ADDITIVE,
+    PROC_THREAD_ATTRIBUTE_INPUT, PROC_THREAD_ATTRIBUTE_NUMBER, PROC_THREAD_ATTRIBUTE_THREAD,
+};
+#[cfg(windows)]
+use crate::{result, wide_nul, Handle};
+#[cfg(any(windows, test))]
+use crate::{Error, ErrorKind, Result, Size};
+
+/// The attribute that attaches a process to a pseudoconsole, which winapi doesn't define.
+#[cfg(any(windows, test))]
+const PROC_THREAD_ATTRIBUTE_PSEUDOCONSOLE: usize = attribute_value(22, false, true, false);
+
+/// Get the attribute with the `number` for `UpdateProcThreadAttribute`, like the
+/// `ProcThreadAttributeValue` macro of the Windows SDK.
+#[cfg(any(windows, test))]
+const fn attribute_value(number: usize, thread: bool, input: bool, additive: bool) -> usize {
+    let mut value = number & PROC_THREAD_ATTRIBUTE_NUMBER;
+    if thread {
+        value |= PROC_THREAD_ATTRIBUTE_THREAD;
+    }
+    if input {
+        value |= PROC_THREAD_ATTRIBUTE_INPUT;
+    }
+    if additive {
+        value |= PROC_THREAD_ATTRIBUTE_ADDITIVE;
+    }
+    value
+}
+
+/// Get the result of a call to the WinAPI function `api` that returns an `HRESULT`, which is
+/// negative on failure.
+///
+/// Failures that wrap a Win32 error code have that code, others have the `HRESULT` as code.
+#[cfg(any(windows, test))]
+fn hresult(api: &'static str, hresult: i32) -> Result<()> {
+    if hresult >= 0 {
+        return Ok(());
+    }
+    let hresult = hresult as u32;
+    let code = if (hresult >> 16) & 0x1fff == FACILITY_WIN32 {
+        hresult & 0xffff
+    } else {
+        hresult
+    };
+    Err(Error::from_code(api, code))
+}
+
+/// Check that `size` is a valid size of a pseudoconsole for the WinAPI function `api`, which is
+/// at least one cell in both dimensions.
+#[cfg(any(windows, test))]
+fn check_size(api: &'static str, size: Size) -> Result<Size> {
+    if size.width < 1 || size.height < 1 {
+        return Err(Error::new(
+            api,
+            ErrorKind::InvalidInput,
+            format!("pseudoconsole size {} must be at least 1x1", size),
+        ));
+    }
+    Ok(size)
+}
+
+/// Allocate the buffer of an attribute list with room for `attribute_count` attributes, and
+/// initialize it.
+///
+/// `initialize` wraps `InitializeProcThreadAttributeList`: it is called without a buffer to get
+/// the size of the list, which fails with `ERROR_INSUFFICIENT_BUFFER`, and then with a buffer of
+/// that size. The buffer consists of `usize`s to be aligned for the list.
+#[cfg(any(windows, test))]
+fn init_attribute_list<F>(attribute_count: u32, mut initialize: F) -> Result<Vec<usize>>
+where
+    F: FnMut(Option<&mut [usize]>, u32, &mut usize) -> Result<()>,
+{
+    let mut size = 0;
+    match initialize(None, attribute_count, &mut size) {
+        Err(error) if error.code() != Some(ERROR_INSUFFICIENT_BUFFER) => return Err(error),
+        _ => {}
+    }
+
+    let mut buffer = vec![0; (size + size_of::<usize>() - 1) / size_of::<usize>()];
+    initialize(Some(&mut buffer), attribute_count, &mut size)?;
+    Ok(buffer)
+}
+
+/// An initialized attribute list for `STARTUPINFOEX`, which is deleted on drop.
+#[cfg(windows)]
+struct AttributeList(Vec<usize>);
+
+#[cfg(windows)]
+impl AttributeList {
+    /// Create an attribute list that attaches a process to the pseudoconsole `console`.
+    ///
+    /// This wraps
+    /// [`InitializeProcThreadAttributeList`](https://docs.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-initializeprocthreadattributelist)
+    /// and
+    /// [`UpdateProcThreadAttribute`](https://docs.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-updateprocthreadattribute).
+    fn with_pseudo_console(console: HPCON) -> Result<AttributeList> {
+        let buffer = init_attribute_list(1, |buffer, count, size| {
+            let list = buffer.map_or(null_mut(), |buffer| buffer.as_mut_ptr());
+            result("InitializeProcThreadAttributeList", unsafe {
+                InitializeProcThreadAttributeList(
+                    list as LPPROC_THREAD_ATTRIBUTE_LIST,
+                    count,
+                    0,
+                    size,
+                )
+            })
+        })?;
+        let mut list = AttributeList(buffer);
+
+        result("UpdateProcThreadAttribute", unsafe {
+            UpdateProcThreadAttribute(
+                list.as_mut_ptr(),
+                0,
+                PROC_THREAD_ATTRIBUTE_PSEUDOCONSOLE,
+                console,
+                size_of::<HPCON>(),
+                null_mut(),
+                null_mut(),
+            )
+        })?;
+        Ok(list)
+    }
+
+    fn as_mut_ptr(&mut self) -> LPPROC_THREAD_ATTRIBUTE_LIST {
+        self.0.as_mut_ptr() as LPPROC_THREAD_ATTRIBUTE_LIST
+    }
+}
+
+#[cfg(windows)]
+impl Drop for AttributeList {
+    fn drop(&mut self) {
+        unsafe { DeleteProcThreadAttributeList(self.as_mut_ptr()) }
+    }
+}
+
+/// Create an anonymous pipe, returning its read and write end.
+///
+/// This wraps
+/// [`CreatePipe`](https://docs.microsoft.com/en-us/windows/win32/api/namedpipeapi/nf-namedpipeapi-createpipe).
+#[cfg(windows)]
+fn pipe() -> Result<(Handle, Handle)> {
+    let mut read = null_mut();
+    let mut write = null_mut();
+    result("CreatePipe", unsafe {
+        CreatePipe(&mut read, &mut write, null_mut(), 0)
+    })?;
+    unsafe { Ok((Handle::from_raw(read), Handle::from_raw(write))) }
+}
+
+/// A [pseudoconsole](https://docs.microsoft.com/en-us/windows/console/pseudoconsoles), which is
+/// closed on drop.
+///
+/// Write input for the console to [`input`](PseudoConsole::input), and read its output, which
+/// contains virtual terminal sequences, from [`output`](PseudoConsole::output). The output has to
+/// be read continuously, from another thread for example, because the console stops when the pipe
+/// is full.
+#[cfg(windows)]
+#[derive(Debug)]
+pub struct PseudoConsole {
+    handle: HPCON,
+    size: Size,
+    input: Handle,
+    output: Handle,
+}
+
+// A pseudoconsole can be used and closed from any thread.
+#[cfg(windows)]
+unsafe impl Send for PseudoConsole {}
+
+#[cfg(windows)]
+unsafe impl Sync for PseudoConsole {}
+
+#[cfg(windows)]
+impl PseudoConsole {
+    /// Create a pseudoconsole of the given size, with new pipes for its input and output.
+    ///
+    /// This wraps
+    /// [`CreatePseudoConsole`](https://docs.microsoft.com/en-us/windows/console/createpseudoconsole).
+    pub fn new(size: Size) -> Result<PseudoConsole> {
+        let size = check_size("CreatePseudoConsole", size)?;
+        let (console_input, input) = pipe()?;
+        let (output, console_output) = pipe()?;
+
+        let mut handle = null_mut();
+        hresult("CreatePseudoConsole", unsafe {
+            CreatePseudoConsole(size.into(), *console_input, *console_output, 0, &mut handle)
+        })
+        .map_err(|e| e.with_argument("size", &size))?;
+
+        // The pseudoconsole has its own duplicates of the ends it uses, which are dropped here.
+        Ok(PseudoConsole {
+            handle,
+            size,
+            input,
+            output,
+        })
+    }
+
+    /// Get the write end of the pipe the pseudoconsole reads its input from.
+    pub fn input(&self) -> &Handle {
+        &self.input
+    }
+
+    /// Get the read end of the pipe the pseudoconsole writes its output to.
+    pub fn output(&self) -> &Handle {
+        &self.output
+    }
+
+    /// Get the size of the pseudoconsole.
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// Resize the pseudoconsole.
+    ///
+    /// This wraps
+    /// [`ResizePseudoConsole`](https://docs.microsoft.com/en-us/windows/console/resizepseudoconsole).
+    pub fn resize(&mut self, size: Size) -> Result<()> {
+        let size = check_size("ResizePseudoConsole", size)?;
+        hresult("ResizePseudoConsole", unsafe {
+            ResizePseudoConsole(self.handle, size.into())
+        })
+        .map_err(|e| e.with_argument("size", &size))?;
+        self.size = size;
+        Ok(())
+    }
+
+    /// Start a child process attached to the pseudoconsole, returning the handle of the process.
+    ///
+    /// `command_line` is passed to the process as it is, so arguments with spaces have to be
+    /// quoted.
+    ///
+    /// This wraps
+    /// [`CreateProcessW`](https://docs.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-createprocessw)
+    /// with a `STARTUPINFOEX` that has the pseudoconsole as attribute.
+    pub fn spawn(&self, command_line: &str) -> Result<Handle> {
+        let error = |e: Error| e.with_argument("command_line", &command_line);
+
+        let mut attributes = AttributeList::with_pseudo_console(self.handle)?;
+        let mut command_line_wide = wide_nul("CreateProcessW", command_line).map_err(error)?;
+
+        let mut startup_info = STARTUPINFOEXW::default();
+        startup_info.StartupInfo.cb = size_of::<STARTUPINFOEXW>() as u32;
+        startup_info.lpAttributeList = attributes.as_mut_ptr();
+        let mut process_info = PROCESS_INFORMATION::default();
+
+        result("CreateProcessW", unsafe {
+            CreateProcessW(
+                null(),
+                command_line_wide.as_mut_ptr(),
+                null_mut(),
+                null_mut(),
+                FALSE,
+                EXTENDED_STARTUPINFO_PRESENT,
+                null_mut::<c_void>(),
+                null(),
+                &mut startup_info.StartupInfo,
+                &mut process_info,
+            )
+        })
+        .map_err(error)?;
+
+        unsafe {
+            drop(Handle::from_raw(process_info.hThread));
+            Ok(Handle::from_raw(process_info.hProcess))
+        }
+    }
+}
+
+#[cfg(windows)]
+impl Drop for PseudoConsole {
+    /// Close the pseudoconsole, which ends the processes attached to it.
+    ///
+    /// This wraps
+    /// [`ClosePseudoConsole`](https://docs.microsoft.com/en-us/windows/console/closepseudoconsole).
+    /// On older versions of Windows this blocks until the output has been read completely.
+    fn drop(&mut self) {
+        unsafe { ClosePseudoConsole(self.handle) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        attribute_value, check_size, hresult, init_attribute_list, ERROR_INSUFFICIENT_BUFFER,
+        PROC_THREAD_ATTRIBUTE_PSEUDOCONSOLE,
+    };
+    use crate::{Error, ErrorKind, Size};
+
+    #[test]
+    fn test_attribute_value() {
+        assert_eq!(PROC_THREAD_ATTRIBUTE_PSEUDOCONSOLE, 0x0002_0016);
+        // `PROC_THREAD_ATTRIBUTE_PARENT_PROCESS` and `PROC_THREAD_ATTRIBUTE_IDEAL_PROCESSOR`.
+        assert_eq!(attribute_value(0, false, true, false), 0x0002_0000);
+        assert_eq!(attribute_value(5, true, true, false), 0x0003_0005);
+    }
+
+    #[test]
+    fn test_hresult() {
+        assert!(hresult("CreatePseudoConsole", 0).is_ok());
+        assert!(hresult("CreatePseudoConsole", 1).is_ok());
+
+        // `E_INVALIDARG` wraps `ERROR_INVALID_PARAMETER`.
+        let error = hresult("CreatePseudoConsole", 0x8007_0057u32 as i32).unwrap_err();
+        assert_eq!(error.code(), Some(87));
+        assert_eq!(error.kind(), ErrorKind::InvalidParameter);
+
+        // `E_UNEXPECTED` doesn't wrap a Win32 error code.
+        let error = hresult("CreatePseudoConsole", 0x8000_ffffu32 as i32).unwrap_err();
+        assert_eq!(error.code(), Some(0x8000_ffff));
+        assert_eq!(error.kind(), ErrorKind::Other);
+    }
+
+    #[test]
+    fn test_check_size() {
+        assert_eq!(
+            check_size("CreatePseudoConsole", Size::new(1, 1)).unwrap(),
+            Size::new(1, 1)
+        );
+        for &(width, height) in &[(0, 25), (80, 0), (-1, 25)] {
+            let error = check_size("ResizePseudoConsole", Size::new(width, height)).unwrap_err();
+            assert_eq!(error.api(), "ResizePseudoConsole");
+            assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn test_init_attribute_list() {
+        let mut calls = Vec::new();
+        let buffer = init_attribute_list(1, |buffer, count, size| {
+            assert_eq!(count, 1);
+            calls.push(buffer.as_ref().map(|buffer| buffer.len()));
+            match buffer {
+                None => {
+                    *size = 44;
+                    Err(Error::from_code(
+                        "InitializeProcThreadAttributeList",
+                        ERROR_INSUFFICIENT_BUFFER,
+                    ))
+                }
+                Some(buffer) => {
+                    assert!(std::mem::size_of_val(buffer) >= *size);
+                    Ok(())
+                }
+            }
+        })
+        .unwrap();
+        assert_eq!(calls, vec![None, Some(buffer.len())]);
+
+        let error = init_attribute_list(1, |_, _, _| {
+            Err(Error::from_code("InitializeProcThreadAttributeList", 87))
+        })
+        .unwrap_err();
+        assert_eq!(error.code(), Some(87));
+    }
+}
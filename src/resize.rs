@@ -0,0 +1,295 @@
+//! This module contains the logic for resizing a console window together with its screen buffer.
+//!
+//! The console requires its window to lie within the screen buffer at all times, so
+//! `SetConsoleScreenBufferSize` fails when the buffer would become smaller than the window, and
+//! `SetConsoleWindowInfo` fails when the window would extend beyond the buffer or become larger
+//! than `GetLargestConsoleWindowSize`. [`ResizePlan`] works out an order of those calls that
+//! never violates these constraints, clamping the window to the largest possible size.
+
+use std::cmp::min;
+use std::error::Error;
+use std::fmt;
+
+use super::{Coord, ScreenBufferInfo, Size, WindowPositions};
+
+/// A single call that is part of a [`ResizePlan`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ResizeStep {
+    /// Change the size of the screen buffer, with
+    /// [`ScreenBuffer::set_size`](crate::ScreenBuffer::set_size).
+    SetBufferSize(Size),
+    /// Change the position and size of the window in absolute buffer coordinates, with
+    /// [`Console::set_console_info`](crate::Console::set_console_info).
+    SetWindow(WindowPositions),
+}
+
+/// The reason a console can not be resized to the requested size.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ResizeError {
+    /// The requested width or height is smaller than one cell.
+    InvalidSize {
+        /// The requested size.
+        requested: Size,
+    },
+    /// The largest possible window, which depends on the font and the size of the display, is
+    /// smaller than one cell, so no window fits.
+    NoWindowFits {
+        /// The largest possible size of the window.
+        largest: Size,
+    },
+}
+
+impl fmt::Display for ResizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ResizeError::InvalidSize { requested } => {
+                write!(f, "console size {} must be at least 1x1", requested)
+            }
+            ResizeError::NoWindowFits { largest } => write!(
+                f,
+                "the largest possible window size {} is smaller than 1x1",
+                largest
+            ),
+        }
+    }
+}
+
+impl Error for ResizeError {}
+
+/// The calls needed to resize both the screen buffer and the window of a console to the same
+/// size, in an order that keeps the window within the buffer after each call.
+///
+/// After resizing, the window shows the whole buffer, starting at its upper-left corner. If the
+/// buffer is larger than the largest possible window, the window is clamped to that size and
+/// shows the upper-left part of the buffer.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ResizePlan {
+    steps: Vec<ResizeStep>,
+}
+
+impl ResizePlan {
+    /// Plan resizing a console that currently looks like `info` to `size`, given the largest
+    /// possible window size as returned by
+    /// [`Console::largest_window_size`](crate::Console::largest_window_size).
+    pub fn new(
+        info: &ScreenBufferInfo,
+        largest_window_size: Size,
+        size: Size,
+    ) -> Result<ResizePlan, ResizeError> {
+        if size.width < 1 || size.height < 1 {
+            return Err(ResizeError::InvalidSize { requested: size });
+        }
+        if largest_window_size.width < 1 || largest_window_size.height < 1 {
+            return Err(ResizeError::NoWindowFits {
+                largest: largest_window_size,
+            });
+        }
+
+        let window_size = Size::new(
+            min(size.width, largest_window_size.width),
+            min(size.height, largest_window_size.height),
+        );
+        let new_window = WindowPositions::from_origin_size(Coord::default(), window_size);
+
+        let steps = if info.window.clamp(size) == Some(info.window) {
+            // The current window fits in the new buffer, so the buffer can be resized first.
+            vec![
+                ResizeStep::SetBufferSize(size),
+                ResizeStep::SetWindow(new_window),
+            ]
+        } else if new_window.clamp(info.buffer_size) == Some(new_window) {
+            // The new window fits in the current buffer, so the window can be resized first.
+            vec![
+                ResizeStep::SetWindow(new_window),
+                ResizeStep::SetBufferSize(size),
+            ]
+        } else {
+            // The buffer grows in one direction and shrinks in the other. Move the window to a
+            // spot that fits in both the current and the new buffer first.
+            let current_size = info.window.size();
+            let intermediate = WindowPositions::from_origin_size(
+                Coord::default(),
+                Size::new(
+                    min(current_size.width, window_size.width),
+                    min(current_size.height, window_size.height),
+                ),
+            );
+            vec![
+                ResizeStep::SetWindow(intermediate),
+                ResizeStep::SetBufferSize(size),
+                ResizeStep::SetWindow(new_window),
+            ]
+        };
+
+        Ok(ResizePlan { steps })
+    }
+
+    /// Get the calls to make, in order.
+    pub fn steps(&self) -> &[ResizeStep] {
+        &self.steps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::{ResizeError, ResizePlan, ResizeStep};
+    use crate::{Coord, ScreenBufferInfo, Size, WindowPositions};
+
+    const LARGEST: Size = Size {
+        width: 200,
+        height: 60,
+    };
+
+    fn info(buffer_size: Size, window: WindowPositions) -> ScreenBufferInfo {
+        ScreenBufferInfo {
+            buffer_size,
+            window,
+            ..ScreenBufferInfo::default()
+        }
+    }
+
+    fn rect(left: i16, top: i16, width: i16, height: i16) -> WindowPositions {
+        WindowPositions::from_origin_size(Coord::new(left, top), Size::new(width, height))
+    }
+
+    /// Apply the steps of a plan the way the console would, failing whenever it would.
+    fn simulate(info: &ScreenBufferInfo, plan: &ResizePlan) -> (Size, WindowPositions) {
+        let (mut buffer, mut window) = (info.buffer_size, info.window);
+
+        for step in plan.steps() {
+            match *step {
+                ResizeStep::SetBufferSize(size) => buffer = size,
+                ResizeStep::SetWindow(rect) => {
+                    assert!(rect.width() <= LARGEST.width && rect.height() <= LARGEST.height);
+                    window = rect;
+                }
+            }
+            assert_eq!(
+                window.clamp(buffer),
+                Some(window),
+                "window {:?} is not within buffer {:?} after {:?}",
+                window,
+                buffer,
+                step
+            );
+        }
+
+        (buffer, window)
+    }
+
+    #[test]
+    fn test_grow_resizes_buffer_first() {
+        let info = info(Size::new(80, 25), rect(0, 0, 80, 25));
+        let plan = ResizePlan::new(&info, LARGEST, Size::new(120, 30)).unwrap();
+
+        assert_eq!(
+            plan.steps(),
+            &[
+                ResizeStep::SetBufferSize(Size::new(120, 30)),
+                ResizeStep::SetWindow(rect(0, 0, 120, 30)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shrink_resizes_window_first() {
+        let info = info(Size::new(120, 9001), rect(0, 8000, 120, 30));
+        let plan = ResizePlan::new(&info, LARGEST, Size::new(80, 25)).unwrap();
+
+        assert_eq!(
+            plan.steps(),
+            &[
+                ResizeStep::SetWindow(rect(0, 0, 80, 25)),
+                ResizeStep::SetBufferSize(Size::new(80, 25)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_grow_and_shrink_moves_window_in_between() {
+        let info = info(Size::new(80, 25), rect(0, 0, 80, 25));
+        let plan = ResizePlan::new(&info, LARGEST, Size::new(100, 20)).unwrap();
+
+        assert_eq!(plan.steps().len(), 3);
+        assert_eq!(
+            simulate(&info, &plan),
+            (Size::new(100, 20), rect(0, 0, 100, 20))
+        );
+    }
+
+    #[test]
+    fn test_invalid_sizes() {
+        let info = info(Size::new(80, 25), rect(0, 0, 80, 25));
+
+        assert_eq!(
+            ResizePlan::new(&info, LARGEST, Size::new(0, 25)),
+            Err(ResizeError::InvalidSize {
+                requested: Size::new(0, 25)
+            })
+        );
+        assert_eq!(
+            ResizePlan::new(&info, Size::new(0, 60), Size::new(80, 25)),
+            Err(ResizeError::NoWindowFits {
+                largest: Size::new(0, 60)
+            })
+        );
+    }
+
+    #[test]
+    fn test_clamps_window_to_largest() {
+        let info = info(Size::new(80, 25), rect(0, 0, 80, 25));
+        let plan = ResizePlan::new(&info, LARGEST, Size::new(300, 9001)).unwrap();
+
+        assert_eq!(
+            plan.steps(),
+            &[
+                ResizeStep::SetBufferSize(Size::new(300, 9001)),
+                ResizeStep::SetWindow(rect(0, 0, 200, 60)),
+            ]
+        );
+    }
+
+    /// A screen buffer with a window somewhere inside of it.
+    fn console() -> impl Strategy<Value = ScreenBufferInfo> {
+        (1i16..300, 1i16..300)
+            .prop_flat_map(|(width, height)| {
+                (
+                    Just(Size::new(width, height)),
+                    1..=width.min(LARGEST.width),
+                    1..=height.min(LARGEST.height),
+                )
+            })
+            .prop_flat_map(|(buffer, width, height)| {
+                (
+                    Just(buffer),
+                    Just(Size::new(width, height)),
+                    0..=buffer.width - width,
+                    0..=buffer.height - height,
+                )
+            })
+            .prop_map(|(buffer, window, left, top)| {
+                info(buffer, rect(left, top, window.width, window.height))
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_plan_keeps_window_in_buffer(
+            info in console(),
+            width in 1i16..400,
+            height in 1i16..400,
+        ) {
+            let plan = ResizePlan::new(&info, LARGEST, Size::new(width, height)).unwrap();
+
+            prop_assert_eq!(
+                simulate(&info, &plan),
+                (
+                    Size::new(width, height),
+                    rect(0, 0, width.min(LARGEST.width), height.min(LARGEST.height))
+                )
+            );
+        }
+    }
+}
@@ -1,23 +1,28 @@
 //! This contains the logic for working with the console buffer.
 
-use std::io::Result;
-use std::mem::size_of;
+use std::mem::{size_of, zeroed};
+use std::os::windows::io::{AsHandle, BorrowedHandle};
 
 use winapi::{
-    shared::minwindef::TRUE,
+    shared::minwindef::{FALSE, TRUE},
     shared::ntdef::NULL,
     um::{
         minwinbase::SECURITY_ATTRIBUTES,
         wincon::{
-            CreateConsoleScreenBuffer, GetConsoleScreenBufferInfo, GetCurrentConsoleFont,
-            SetConsoleActiveScreenBuffer, SetConsoleScreenBufferSize, CONSOLE_TEXTMODE_BUFFER,
-            COORD,
+            CreateConsoleScreenBuffer, GetConsoleFontSize, GetConsoleScreenBufferInfo,
+            GetConsoleScreenBufferInfoEx, GetCurrentConsoleFont, GetCurrentConsoleFontEx,
+            SetConsoleActiveScreenBuffer, SetConsoleScreenBufferInfoEx, SetConsoleScreenBufferSize,
+            SetCurrentConsoleFontEx, CONSOLE_FONT_INFOEX, CONSOLE_SCREEN_BUFFER_INFO,
+            CONSOLE_SCREEN_BUFFER_INFOEX, CONSOLE_TEXTMODE_BUFFER, COORD,
         },
         winnt::{FILE_SHARE_READ, FILE_SHARE_WRITE, GENERIC_READ, GENERIC_WRITE},
     },
 };
 
-use super::{handle_result, result, FontInfo, Handle, HandleType, ScreenBufferInfo};
+use super::{
+    coord_result, handle_result, result, Console, Error, ErrorKind, FontInfo, FontInfoEx, Handle,
+    HandleType, ResizePlan, ResizeStep, Result, ScreenBufferInfo, ScreenBufferInfoEx, Size,
+};
 
 /// A wrapper around a screen buffer.
 #[derive(Clone, Debug)]
@@ -49,7 +54,7 @@ impl ScreenBuffer {
             bInheritHandle: TRUE,
         };
 
-        let new_screen_buffer = handle_result(unsafe {
+        let new_screen_buffer = handle_result("CreateConsoleScreenBuffer", unsafe {
             CreateConsoleScreenBuffer(
                 GENERIC_READ |           // read/write access
                     GENERIC_WRITE,
@@ -69,7 +74,10 @@ impl ScreenBuffer {
     /// This wraps
     /// [`SetConsoleActiveScreenBuffer`](https://docs.microsoft.com/en-us/windows/console/setconsoleactivescreenbuffer).
     pub fn show(&self) -> Result<()> {
-        result(unsafe { SetConsoleActiveScreenBuffer(*self.handle) })
+        result("SetConsoleActiveScreenBuffer", unsafe {
+            SetConsoleActiveScreenBuffer(*self.handle)
+        })
+        .map_err(|e| e.with_handle(&self.handle))
     }
 
     /// Get the screen buffer information like terminal size, cursor position, buffer size.
@@ -77,27 +85,169 @@ impl ScreenBuffer {
     /// This wraps
     /// [`GetConsoleScreenBufferInfo`](https://docs.microsoft.com/en-us/windows/console/getconsolescreenbufferinfo).
     pub fn info(&self) -> Result<ScreenBufferInfo> {
-        let mut csbi = ScreenBufferInfo::new();
-        result(unsafe { GetConsoleScreenBufferInfo(*self.handle, &mut csbi.0) })?;
-        Ok(csbi)
+        let mut csbi: CONSOLE_SCREEN_BUFFER_INFO = unsafe { zeroed() };
+        result("GetConsoleScreenBufferInfo", unsafe {
+            GetConsoleScreenBufferInfo(*self.handle, &mut csbi)
+        })
+        .map_err(|e| e.with_handle(&self.handle))?;
+        Ok(ScreenBufferInfo::from(csbi))
+    }
+
+    /// Get the extended screen buffer information, which also contains the color table, the
+    /// popup attributes and whether full-screen mode is supported.
+    ///
+    /// This wraps
+    /// [`GetConsoleScreenBufferInfoEx`](https://docs.microsoft.com/en-us/windows/console/getconsolescreenbufferinfoex).
+    pub fn info_ex(&self) -> Result<ScreenBufferInfoEx> {
+        let mut csbi = ScreenBufferInfoEx::raw();
+        result("GetConsoleScreenBufferInfoEx", unsafe {
+            GetConsoleScreenBufferInfoEx(*self.handle, &mut csbi)
+        })
+        .map_err(|e| e.with_handle(&self.handle))?;
+        Ok(ScreenBufferInfoEx::from(csbi))
+    }
+
+    /// Set the extended screen buffer information, for example to change the color table.
+    ///
+    /// To apply a theme and restore the original palette afterwards, keep the value returned by
+    /// [`ScreenBuffer::info_ex`], and pass it to this function again when done.
+    ///
+    /// `SetConsoleScreenBufferInfoEx` treats the right and bottom edges of the window as
+    /// exclusive, while `GetConsoleScreenBufferInfoEx` reports them inclusive. This function
+    /// compensates for that, so the window keeps its size when passing information obtained
+    /// from [`ScreenBuffer::info_ex`]. A window whose right or bottom edge is `i16::MAX` can't
+    /// be passed that way and results in an error of kind
+    /// [`InvalidInput`](ErrorKind::InvalidInput).
+    ///
+    /// This wraps
+    /// [`SetConsoleScreenBufferInfoEx`](https://docs.microsoft.com/en-us/windows/console/setconsolescreenbufferinfoex).
+    pub fn set_info_ex(&self, info: &ScreenBufferInfoEx) -> Result<()> {
+        let mut csbi = CONSOLE_SCREEN_BUFFER_INFOEX::from(*info);
+        let (right, bottom) = match (
+            csbi.srWindow.Right.checked_add(1),
+            csbi.srWindow.Bottom.checked_add(1),
+        ) {
+            (Some(right), Some(bottom)) => (right, bottom),
+            _ => {
+                return Err(Error::new(
+                    "SetConsoleScreenBufferInfoEx",
+                    ErrorKind::InvalidInput,
+                    "window edge is too large to be passed as exclusive edge",
+                )
+                .with_argument("info", info))
+            }
+        };
+        csbi.srWindow.Right = right;
+        csbi.srWindow.Bottom = bottom;
+        result("SetConsoleScreenBufferInfoEx", unsafe {
+            SetConsoleScreenBufferInfoEx(*self.handle, &mut csbi)
+        })
+        .map_err(|e| e.with_handle(&self.handle).with_argument("info", info))
     }
 
     /// Get the current font information like size and font index.
     ///
     /// This wraps
-    /// [`GetConsoleFontSize`](https://learn.microsoft.com/en-us/windows/console/getconsolefontsize).
+    /// [`GetCurrentConsoleFont`](https://learn.microsoft.com/en-us/windows/console/getcurrentconsolefont).
     pub fn font_info(&self) -> Result<FontInfo> {
         let mut fi = FontInfo::new();
-        result(unsafe { GetCurrentConsoleFont(*self.handle, 0, &mut fi.0) })?;
+        result("GetCurrentConsoleFont", unsafe {
+            GetCurrentConsoleFont(*self.handle, 0, &mut fi.0)
+        })
+        .map_err(|e| e.with_handle(&self.handle))?;
         Ok(fi)
     }
 
+    /// Get extended information about the current font, including its face name, weight and
+    /// family.
+    ///
+    /// This wraps
+    /// [`GetCurrentConsoleFontEx`](https://learn.microsoft.com/en-us/windows/console/getcurrentconsolefontex).
+    pub fn font_info_ex(&self) -> Result<FontInfoEx> {
+        let mut cfi = FontInfoEx::raw();
+        result("GetCurrentConsoleFontEx", unsafe {
+            GetCurrentConsoleFontEx(*self.handle, FALSE, &mut cfi)
+        })
+        .map_err(|e| e.with_handle(&self.handle))?;
+        Ok(FontInfoEx::from(cfi))
+    }
+
+    /// Set the current font, for example to a TrueType font by its face name.
+    ///
+    /// The console picks the installed font that matches best, so read the font back with
+    /// [`ScreenBuffer::font_info_ex`] to see which one it is.
+    ///
+    /// This wraps
+    /// [`SetCurrentConsoleFontEx`](https://learn.microsoft.com/en-us/windows/console/setcurrentconsolefontex).
+    pub fn set_font_info_ex(&self, info: &FontInfoEx) -> Result<()> {
+        let mut cfi = CONSOLE_FONT_INFOEX::from(info);
+        result("SetCurrentConsoleFontEx", unsafe {
+            SetCurrentConsoleFontEx(*self.handle, FALSE, &mut cfi)
+        })
+        .map_err(|e| e.with_handle(&self.handle).with_argument("info", info))
+    }
+
+    /// Get the width and height in pixels of a character cell of the font with the `index` in
+    /// the system's console font table, like the one of [`FontInfo::index`].
+    ///
+    /// This wraps
+    /// [`GetConsoleFontSize`](https://learn.microsoft.com/en-us/windows/console/getconsolefontsize).
+    pub fn font_size(&self, index: u32) -> Result<Size> {
+        let size = coord_result("GetConsoleFontSize", unsafe {
+            GetConsoleFontSize(*self.handle, index)
+        })
+        .map_err(|e| e.with_handle(&self.handle).with_argument("index", &index))?;
+        Ok(Size::new(size.x, size.y))
+    }
+
     /// Set the console screen buffer size to the given size.
     ///
     /// This wraps
     /// [`SetConsoleScreenBufferSize`](https://docs.microsoft.com/en-us/windows/console/setconsolescreenbuffersize).
     pub fn set_size(&self, x: i16, y: i16) -> Result<()> {
-        result(unsafe { SetConsoleScreenBufferSize(*self.handle, COORD { X: x, Y: y }) })
+        result("SetConsoleScreenBufferSize", unsafe {
+            SetConsoleScreenBufferSize(*self.handle, COORD { X: x, Y: y })
+        })
+        .map_err(|e| {
+            e.with_handle(&self.handle)
+                .with_argument("x", &x)
+                .with_argument("y", &y)
+        })
+    }
+
+    /// Resize both the screen buffer and the window to `width` columns and `height` rows.
+    ///
+    /// The buffer and window have to be resized in a certain order, because the buffer can never
+    /// be smaller than the window; see [`ResizePlan`] for how that order is determined. After
+    /// resizing, the window shows the whole buffer, or as much of it as fits in
+    /// [`Console::largest_window_size`].
+    ///
+    /// If the size is smaller than one cell, or no window fits, this returns an
+    /// [`Error::validation`] error for `"ScreenBuffer::resize"` whose source is a
+    /// [`ResizeError`](crate::ResizeError) describing the problem, without resizing anything.
+    pub fn resize(&self, width: i16, height: i16) -> Result<()> {
+        let console = Console::from(self.handle.clone());
+        let largest = console.largest_window_size()?;
+
+        let plan = ResizePlan::new(
+            &self.info()?,
+            Size::new(largest.x, largest.y),
+            Size::new(width, height),
+        )
+        .map_err(|e| {
+            Error::validation("ScreenBuffer::resize", e)
+                .with_handle(&self.handle)
+                .with_argument("width", &width)
+                .with_argument("height", &height)
+        })?;
+
+        for step in plan.steps() {
+            match *step {
+                ResizeStep::SetBufferSize(size) => self.set_size(size.width, size.height)?,
+                ResizeStep::SetWindow(window) => console.set_console_info(true, window)?,
+            }
+        }
+        Ok(())
     }
 
     /// Get the underlying raw `HANDLE` used by this type to execute with.
@@ -106,6 +256,12 @@ impl ScreenBuffer {
     }
 }
 
+impl AsHandle for ScreenBuffer {
+    fn as_handle(&self) -> BorrowedHandle<'_> {
+        self.handle.as_handle()
+    }
+}
+
 impl From<Handle> for ScreenBuffer {
     fn from(handle: Handle) -> Self {
         ScreenBuffer { handle }
@@ -1,33 +1,224 @@
-use std::{io, ptr};
+//! This module contains the Windows semaphore wrapper and [`LocalSemaphore`], a semaphore with
+//! the same behaviour that works on every platform and can stand in for it in tests.
+//!
+//! A semaphore has a count between zero and its maximum count. Waiting on it blocks while the
+//! count is zero, and decrements the count once it is above zero. Releasing it increments the
+//! count, but never above the maximum: a release that would exceed it fails with
+//! [`SemaphoreError::MaximumExceeded`] and leaves the count as it is.
 
-use winapi::um::synchapi::{CreateSemaphoreW, ReleaseSemaphore};
+use std::error;
+use std::fmt;
+use std::io;
+#[cfg(windows)]
+use std::os::windows::io::{AsHandle, BorrowedHandle};
+#[cfg(windows)]
+use std::ptr;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
 
-use crate::{nonnull_handle_result, result, Handle};
+#[cfg(windows)]
+use winapi::shared::minwindef::FALSE;
+#[cfg(windows)]
+use winapi::um::synchapi::{CreateSemaphoreW, OpenSemaphoreW, ReleaseSemaphore};
+#[cfg(windows)]
+use winapi::um::winnt::{SEMAPHORE_MODIFY_STATE, SYNCHRONIZE};
+
+#[cfg(windows)]
+use crate::wait::wait_one;
+use crate::win32::ERROR_TOO_MANY_POSTS;
+#[cfg(windows)]
+use crate::{nonnull_handle_result, result, wide_nul, Handle, WaitResult, Waitable};
+use crate::{Error, ErrorKind};
+
+/// The largest count a semaphore can have, because WinAPI takes counts as a `LONG`.
+const MAXIMUM_COUNT: u32 = i32::MAX as u32;
+
+/// An error of a semaphore operation.
+#[derive(Debug)]
+pub enum SemaphoreError {
+    /// The maximum count is zero or too large, or the initial count is larger than the maximum
+    /// count.
+    InvalidCounts {
+        /// The initial count that was asked for.
+        initial_count: u32,
+        /// The maximum count that was asked for.
+        maximum_count: u32,
+    },
+    /// The count to release is zero or too large.
+    InvalidReleaseCount(u32),
+    /// Releasing would raise the count of the semaphore above its maximum count.
+    MaximumExceeded,
+    /// A call to WinAPI failed.
+    Os(Error),
+}
+
+impl fmt::Display for SemaphoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SemaphoreError::InvalidCounts {
+                initial_count,
+                maximum_count,
+            } => write!(
+                f,
+                "invalid semaphore counts: initial count {} and maximum count {}",
+                initial_count, maximum_count
+            ),
+            SemaphoreError::InvalidReleaseCount(count) => {
+                write!(f, "invalid semaphore release count {}", count)
+            }
+            SemaphoreError::MaximumExceeded => {
+                f.write_str("releasing the semaphore would exceed its maximum count")
+            }
+            SemaphoreError::Os(error) => error.fmt(f),
+        }
+    }
+}
+
+impl error::Error for SemaphoreError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            SemaphoreError::Os(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<Error> for SemaphoreError {
+    /// Convert an error of a call to WinAPI, recognizing `ERROR_TOO_MANY_POSTS` as
+    /// [`MaximumExceeded`](SemaphoreError::MaximumExceeded).
+    fn from(error: Error) -> Self {
+        match error.code() {
+            Some(ERROR_TOO_MANY_POSTS) => SemaphoreError::MaximumExceeded,
+            _ => SemaphoreError::Os(error),
+        }
+    }
+}
+
+impl From<SemaphoreError> for Error {
+    fn from(error: SemaphoreError) -> Self {
+        match error {
+            SemaphoreError::Os(error) => error,
+            SemaphoreError::InvalidCounts { .. } => {
+                Error::new("CreateSemaphoreW", ErrorKind::InvalidInput, error)
+            }
+            SemaphoreError::InvalidReleaseCount(_) => {
+                Error::new("ReleaseSemaphore", ErrorKind::InvalidInput, error)
+            }
+            SemaphoreError::MaximumExceeded => {
+                Error::new("ReleaseSemaphore", ErrorKind::Other, error)
+            }
+        }
+    }
+}
+
+impl From<SemaphoreError> for io::Error {
+    fn from(error: SemaphoreError) -> Self {
+        match error {
+            SemaphoreError::Os(error) => error.into(),
+            SemaphoreError::MaximumExceeded => io::Error::new(io::ErrorKind::Other, error),
+            _ => io::Error::new(io::ErrorKind::InvalidInput, error),
+        }
+    }
+}
+
+fn check_counts(initial_count: u32, maximum_count: u32) -> Result<(), SemaphoreError> {
+    if maximum_count == 0 || maximum_count > MAXIMUM_COUNT || initial_count > maximum_count {
+        Err(SemaphoreError::InvalidCounts {
+            initial_count,
+            maximum_count,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+fn check_release_count(count: u32) -> Result<(), SemaphoreError> {
+    if count == 0 || count > MAXIMUM_COUNT {
+        Err(SemaphoreError::InvalidReleaseCount(count))
+    } else {
+        Ok(())
+    }
+}
 
 /// A [Windows semaphore](https://docs.microsoft.com/en-us/windows/win32/sync/semaphore-objects).
+///
+/// Clones refer to the same semaphore.
+#[cfg(windows)]
 #[derive(Clone, Debug)]
 pub struct Semaphore(Handle);
 
+#[cfg(windows)]
 impl Semaphore {
-    /// Construct a new semaphore.
+    /// Construct a new semaphore with a count of 0 and a maximum count of 1.
     ///
     /// This wraps
     /// [`CreateSemaphoreW`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-createsemaphorew).
-    pub fn new() -> io::Result<Self> {
-        let handle = nonnull_handle_result(unsafe {
-            CreateSemaphoreW(ptr::null_mut(), 0, 1, ptr::null_mut())
-        })?;
+    pub fn new() -> crate::Result<Self> {
+        Ok(Semaphore::builder().build()?)
+    }
+
+    /// Start building a semaphore with other counts or a name.
+    pub fn builder() -> SemaphoreBuilder {
+        SemaphoreBuilder::new()
+    }
+
+    /// Open an existing named semaphore.
+    ///
+    /// This wraps
+    /// [`OpenSemaphoreW`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-opensemaphorew).
+    pub fn open(name: &str) -> crate::Result<Self> {
+        let error = |e: Error| e.with_argument("name", &name);
+        let wide_name = wide_nul("OpenSemaphoreW", name).map_err(error)?;
+        let handle = nonnull_handle_result("OpenSemaphoreW", unsafe {
+            OpenSemaphoreW(
+                SYNCHRONIZE | SEMAPHORE_MODIFY_STATE,
+                FALSE,
+                wide_name.as_ptr(),
+            )
+        })
+        .map_err(error)?;
 
         let handle = unsafe { Handle::from_raw(handle) };
         Ok(Self(handle))
     }
 
-    /// Release a permit on the semaphore.
+    /// Wait until the count is above zero, and decrement it.
+    ///
+    /// This wraps
+    /// [`WaitForSingleObject`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-waitforsingleobject).
+    pub fn wait(&self) -> crate::Result<()> {
+        wait_one(&self.0, None)?;
+        Ok(())
+    }
+
+    /// Wait at most `timeout` until the count is above zero, and decrement it. Returns whether
+    /// the count was decremented.
+    ///
+    /// This wraps
+    /// [`WaitForSingleObject`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-waitforsingleobject).
+    pub fn wait_timeout(&self, timeout: Duration) -> crate::Result<bool> {
+        Ok(wait_one(&self.0, Some(timeout))? != WaitResult::TimedOut)
+    }
+
+    /// Decrement the count if it is above zero, without waiting. Returns whether the count was
+    /// decremented.
+    pub fn try_wait(&self) -> crate::Result<bool> {
+        self.wait_timeout(Duration::from_secs(0))
+    }
+
+    /// Increment the count by `count`, returning the count from before.
     ///
     /// This wraps
     /// [`ReleaseSemaphore`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-releasesemaphore).
-    pub fn release(&self) -> io::Result<()> {
-        result(unsafe { ReleaseSemaphore(*self.0, 1, ptr::null_mut()) })
+    pub fn release(&self, count: u32) -> Result<u32, SemaphoreError> {
+        check_release_count(count)?;
+
+        let mut previous_count = 0;
+        result("ReleaseSemaphore", unsafe {
+            ReleaseSemaphore(*self.0, count as i32, &mut previous_count)
+        })
+        .map_err(|e| e.with_handle(&self.0).with_argument("count", &count))?;
+        Ok(previous_count as u32)
     }
 
     /// Access the underlying handle to the semaphore.
@@ -36,6 +227,297 @@ impl Semaphore {
     }
 }
 
+#[cfg(windows)]
+impl AsHandle for Semaphore {
+    fn as_handle(&self) -> BorrowedHandle<'_> {
+        self.0.as_handle()
+    }
+}
+
+#[cfg(windows)]
+impl Waitable for Semaphore {
+    fn handle(&self) -> &Handle {
+        &self.0
+    }
+}
+
+#[cfg(windows)]
 unsafe impl Send for Semaphore {}
 
+#[cfg(windows)]
 unsafe impl Sync for Semaphore {}
+
+/// A builder for a [`Semaphore`] with other counts than the default count of 0 and maximum
+/// count of 1, or with a name.
+#[cfg(windows)]
+#[derive(Clone, Debug)]
+pub struct SemaphoreBuilder {
+    initial_count: u32,
+    maximum_count: u32,
+    name: Option<String>,
+}
+
+#[cfg(windows)]
+impl SemaphoreBuilder {
+    /// Create a builder for a semaphore with a count of 0 and a maximum count of 1.
+    pub fn new() -> SemaphoreBuilder {
+        SemaphoreBuilder {
+            initial_count: 0,
+            maximum_count: 1,
+            name: None,
+        }
+    }
+
+    /// Set the count the semaphore starts with.
+    pub fn initial_count(mut self, initial_count: u32) -> SemaphoreBuilder {
+        self.initial_count = initial_count;
+        self
+    }
+
+    /// Set the maximum count of the semaphore.
+    pub fn maximum_count(mut self, maximum_count: u32) -> SemaphoreBuilder {
+        self.maximum_count = maximum_count;
+        self
+    }
+
+    /// Set the name of the semaphore, so other processes can open it with [`Semaphore::open`].
+    ///
+    /// If a semaphore with this name already exists, building opens that semaphore and the
+    /// counts of the builder are ignored.
+    pub fn name(mut self, name: &str) -> SemaphoreBuilder {
+        self.name = Some(name.to_owned());
+        self
+    }
+
+    /// Create the semaphore.
+    ///
+    /// This wraps
+    /// [`CreateSemaphoreW`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-createsemaphorew).
+    pub fn build(&self) -> Result<Semaphore, SemaphoreError> {
+        check_counts(self.initial_count, self.maximum_count)?;
+
+        let error = |e: Error| {
+            e.with_argument("initial_count", &self.initial_count)
+                .with_argument("maximum_count", &self.maximum_count)
+                .with_argument("name", &self.name)
+        };
+        let name = self
+            .name
+            .as_deref()
+            .map(|name| wide_nul("CreateSemaphoreW", name))
+            .transpose()
+            .map_err(error)?;
+        let handle = nonnull_handle_result("CreateSemaphoreW", unsafe {
+            CreateSemaphoreW(
+                ptr::null_mut(),
+                self.initial_count as i32,
+                self.maximum_count as i32,
+                name.as_ref().map_or(ptr::null(), |name| name.as_ptr()),
+            )
+        })
+        .map_err(error)?;
+
+        let handle = unsafe { Handle::from_raw(handle) };
+        Ok(Semaphore(handle))
+    }
+}
+
+#[cfg(windows)]
+impl Default for SemaphoreBuilder {
+    fn default() -> Self {
+        SemaphoreBuilder::new()
+    }
+}
+
+#[derive(Debug)]
+struct LocalInner {
+    count: Mutex<u32>,
+    released: Condvar,
+    maximum_count: u32,
+}
+
+/// A semaphore within the current process that behaves like [`Semaphore`](crate::Semaphore).
+///
+/// It is available on every platform, so code that uses a semaphore can be tested with this one
+/// in its place. Clones refer to the same semaphore.
+#[derive(Clone, Debug)]
+pub struct LocalSemaphore(Arc<LocalInner>);
+
+impl LocalSemaphore {
+    /// Construct a new semaphore with a count of 0 and a maximum count of 1.
+    pub fn new() -> LocalSemaphore {
+        LocalSemaphore::with_counts(0, 1).unwrap()
+    }
+
+    /// Construct a new semaphore with the given initial and maximum count.
+    pub fn with_counts(
+        initial_count: u32,
+        maximum_count: u32,
+    ) -> Result<LocalSemaphore, SemaphoreError> {
+        check_counts(initial_count, maximum_count)?;
+
+        Ok(LocalSemaphore(Arc::new(LocalInner {
+            count: Mutex::new(initial_count),
+            released: Condvar::new(),
+            maximum_count,
+        })))
+    }
+
+    /// Wait until the count is above zero, and decrement it.
+    pub fn wait(&self) {
+        let mut count = self
+            .0
+            .released
+            .wait_while(self.count(), |count| *count == 0)
+            .unwrap_or_else(PoisonError::into_inner);
+        *count -= 1;
+    }
+
+    /// Wait at most `timeout` until the count is above zero, and decrement it. Returns whether
+    /// the count was decremented.
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let (mut count, _) = self
+            .0
+            .released
+            .wait_timeout_while(self.count(), timeout, |count| *count == 0)
+            .unwrap_or_else(PoisonError::into_inner);
+
+        if *count == 0 {
+            false
+        } else {
+            *count -= 1;
+            true
+        }
+    }
+
+    /// Decrement the count if it is above zero, without waiting. Returns whether the count was
+    /// decremented.
+    pub fn try_wait(&self) -> bool {
+        self.wait_timeout(Duration::from_secs(0))
+    }
+
+    /// Increment the count by `count`, returning the count from before.
+    pub fn release(&self, count: u32) -> Result<u32, SemaphoreError> {
+        check_release_count(count)?;
+
+        let mut current = self.count();
+        let previous_count = *current;
+        if count > self.0.maximum_count - previous_count {
+            return Err(SemaphoreError::MaximumExceeded);
+        }
+
+        *current += count;
+        self.0.released.notify_all();
+        Ok(previous_count)
+    }
+
+    fn count(&self) -> MutexGuard<'_, u32> {
+        self.0.count.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for LocalSemaphore {
+    fn default() -> Self {
+        LocalSemaphore::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::{LocalSemaphore, SemaphoreError};
+    use crate::Error;
+
+    #[test]
+    fn test_counts() {
+        let semaphore = LocalSemaphore::with_counts(1, 3).unwrap();
+        assert!(semaphore.try_wait());
+        assert!(!semaphore.try_wait());
+
+        assert_eq!(semaphore.release(2).unwrap(), 0);
+        assert_eq!(semaphore.release(1).unwrap(), 2);
+        assert!(matches!(
+            semaphore.release(1),
+            Err(SemaphoreError::MaximumExceeded)
+        ));
+
+        semaphore.wait();
+        assert!(semaphore.wait_timeout(Duration::from_millis(1)));
+        assert!(semaphore.try_wait());
+        assert!(!semaphore.wait_timeout(Duration::from_millis(1)));
+    }
+
+    #[test]
+    fn test_invalid_counts() {
+        for &(initial_count, maximum_count) in &[(0, 0), (2, 1), (0, 0x8000_0000)] {
+            assert!(matches!(
+                LocalSemaphore::with_counts(initial_count, maximum_count),
+                Err(SemaphoreError::InvalidCounts { .. })
+            ));
+        }
+
+        let semaphore = LocalSemaphore::new();
+        assert!(matches!(
+            semaphore.release(0),
+            Err(SemaphoreError::InvalidReleaseCount(0))
+        ));
+    }
+
+    #[test]
+    fn test_wait_is_woken_by_release() {
+        let semaphore = LocalSemaphore::new();
+        let waiter = {
+            let semaphore = semaphore.clone();
+            thread::spawn(move || semaphore.wait())
+        };
+
+        semaphore.release(1).unwrap();
+        waiter.join().unwrap();
+        assert!(!semaphore.try_wait());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_semaphore_counts() {
+        use super::Semaphore;
+
+        let semaphore = Semaphore::builder()
+            .initial_count(1)
+            .maximum_count(3)
+            .build()
+            .unwrap();
+        assert!(semaphore.try_wait().unwrap());
+        assert!(!semaphore.try_wait().unwrap());
+
+        assert_eq!(semaphore.release(2).unwrap(), 0);
+        assert_eq!(semaphore.release(1).unwrap(), 2);
+        assert!(matches!(
+            semaphore.release(1),
+            Err(SemaphoreError::MaximumExceeded)
+        ));
+
+        semaphore.wait().unwrap();
+        assert!(semaphore.wait_timeout(Duration::from_millis(1)).unwrap());
+        assert!(semaphore.try_wait().unwrap());
+        assert!(!semaphore.wait_timeout(Duration::from_millis(1)).unwrap());
+    }
+
+    #[test]
+    fn test_error_conversions() {
+        assert!(matches!(
+            SemaphoreError::from(Error::from_code("ReleaseSemaphore", 298)),
+            SemaphoreError::MaximumExceeded
+        ));
+        assert!(matches!(
+            SemaphoreError::from(Error::from_code("ReleaseSemaphore", 6)),
+            SemaphoreError::Os(_)
+        ));
+
+        let error = io::Error::from(SemaphoreError::InvalidReleaseCount(0));
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(error.to_string(), "invalid semaphore release count 0");
+    }
+}
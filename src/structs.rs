@@ -1,11 +1,19 @@
+pub use self::attributes::Attributes;
+pub use self::color_table::{ColorTable, Rgb};
+pub use self::conversion::ConversionError;
 pub use self::coord::Coord;
-pub use self::input::{
-    ButtonState, ControlKeyState, EventFlags, InputRecord, KeyEventRecord, MouseEvent,
-};
+#[cfg(windows)]
+pub use self::input::{ButtonState, EventFlags, InputRecord, MouseEvent};
+pub use self::key_event::{ControlKeyState, KeyEventRecord};
 pub use self::size::Size;
 pub use self::window_coords::WindowPositions;
 
+mod attributes;
+mod color_table;
+mod conversion;
 mod coord;
+#[cfg(windows)]
 mod input;
+mod key_event;
 mod size;
 mod window_coords;
@@ -0,0 +1,159 @@
+//! This module provides a type that represents the attributes of a character cell.
+//! For example, in WinAPI we have a `WORD` bitmask of colors and other flags for this.
+//! This module provides some functions who will make reading and changing those attributes easier.
+
+use std::fmt;
+use std::ops::{BitAnd, BitOr, BitOrAssign};
+
+/// The [character
+/// attributes](https://docs.microsoft.com/en-us/windows/console/console-screen-buffers#character-attributes)
+/// of a cell, like its foreground and background color.
+///
+/// The foreground color is stored in bits 0-3 and the background color in bits 4-7, both as an
+/// index into the [`ColorTable`](crate::ColorTable).
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Attributes(u16);
+
+impl Attributes {
+    /// Text color contains blue.
+    pub const FOREGROUND_BLUE: Attributes = Attributes(0x0001);
+    /// Text color contains green.
+    pub const FOREGROUND_GREEN: Attributes = Attributes(0x0002);
+    /// Text color contains red.
+    pub const FOREGROUND_RED: Attributes = Attributes(0x0004);
+    /// Text color is intensified.
+    pub const FOREGROUND_INTENSITY: Attributes = Attributes(0x0008);
+    /// Background color contains blue.
+    pub const BACKGROUND_BLUE: Attributes = Attributes(0x0010);
+    /// Background color contains green.
+    pub const BACKGROUND_GREEN: Attributes = Attributes(0x0020);
+    /// Background color contains red.
+    pub const BACKGROUND_RED: Attributes = Attributes(0x0040);
+    /// Background color is intensified.
+    pub const BACKGROUND_INTENSITY: Attributes = Attributes(0x0080);
+    /// Leading byte of a double-byte character.
+    pub const COMMON_LVB_LEADING_BYTE: Attributes = Attributes(0x0100);
+    /// Trailing byte of a double-byte character.
+    pub const COMMON_LVB_TRAILING_BYTE: Attributes = Attributes(0x0200);
+    /// Top horizontal grid line.
+    pub const COMMON_LVB_GRID_HORIZONTAL: Attributes = Attributes(0x0400);
+    /// Left vertical grid line.
+    pub const COMMON_LVB_GRID_LVERTICAL: Attributes = Attributes(0x0800);
+    /// Right vertical grid line.
+    pub const COMMON_LVB_GRID_RVERTICAL: Attributes = Attributes(0x1000);
+    /// Reverse foreground and background attributes.
+    pub const COMMON_LVB_REVERSE_VIDEO: Attributes = Attributes(0x4000);
+    /// Underscore.
+    pub const COMMON_LVB_UNDERSCORE: Attributes = Attributes(0x8000);
+
+    const FOREGROUND_MASK: u16 = 0x000F;
+    const BACKGROUND_MASK: u16 = 0x00F0;
+
+    /// Create attributes from their raw bitmask.
+    pub fn from_bits(bits: u16) -> Attributes {
+        Attributes(bits)
+    }
+
+    /// Get the raw bitmask of these attributes.
+    pub fn bits(self) -> u16 {
+        self.0
+    }
+
+    /// Whether all the bits set in `other` are also set in these attributes.
+    pub fn contains(self, other: Attributes) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Get the index of the foreground color in the color table (0-15).
+    pub fn foreground(self) -> u8 {
+        (self.0 & Self::FOREGROUND_MASK) as u8
+    }
+
+    /// Get the index of the background color in the color table (0-15).
+    pub fn background(self) -> u8 {
+        ((self.0 & Self::BACKGROUND_MASK) >> 4) as u8
+    }
+
+    /// Replace the foreground color with the color at the given index of the color table.
+    ///
+    /// Only the lower four bits of `index` are used.
+    pub fn with_foreground(self, index: u8) -> Attributes {
+        Attributes(self.0 & !Self::FOREGROUND_MASK | (index as u16 & Self::FOREGROUND_MASK))
+    }
+
+    /// Replace the background color with the color at the given index of the color table.
+    ///
+    /// Only the lower four bits of `index` are used.
+    pub fn with_background(self, index: u8) -> Attributes {
+        Attributes(self.0 & !Self::BACKGROUND_MASK | (index as u16 & 0x000F) << 4)
+    }
+}
+
+impl From<u16> for Attributes {
+    fn from(bits: u16) -> Self {
+        Attributes(bits)
+    }
+}
+
+impl From<Attributes> for u16 {
+    fn from(attributes: Attributes) -> Self {
+        attributes.0
+    }
+}
+
+impl BitOr for Attributes {
+    type Output = Attributes;
+
+    fn bitor(self, rhs: Attributes) -> Attributes {
+        Attributes(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Attributes {
+    fn bitor_assign(&mut self, rhs: Attributes) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for Attributes {
+    type Output = Attributes;
+
+    fn bitand(self, rhs: Attributes) -> Attributes {
+        Attributes(self.0 & rhs.0)
+    }
+}
+
+impl fmt::LowerHex for Attributes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+impl fmt::UpperHex for Attributes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Attributes;
+
+    #[test]
+    fn test_attribute_colors() {
+        let attributes = Attributes::FOREGROUND_RED
+            | Attributes::FOREGROUND_INTENSITY
+            | Attributes::BACKGROUND_BLUE
+            | Attributes::COMMON_LVB_UNDERSCORE;
+
+        assert_eq!(attributes.foreground(), 12);
+        assert_eq!(attributes.background(), 1);
+        assert!(attributes.contains(Attributes::COMMON_LVB_UNDERSCORE));
+
+        let attributes = attributes.with_foreground(7).with_background(0x1E);
+        assert_eq!(attributes.foreground(), 7);
+        assert_eq!(attributes.background(), 14);
+        assert_eq!(attributes.bits(), 0x80E7);
+        assert_eq!(format!("{:#06x}", attributes), "0x80e7");
+    }
+}
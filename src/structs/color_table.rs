@@ -0,0 +1,153 @@
+//! This module provides types that represent the console's color palette.
+//! For example, in WinAPI we have `COLORREF` which packs a color into a `u32` as `0x00BBGGRR`.
+//! This module provides some functions who will make reading and changing the palette easier.
+
+use std::ops::{Index, IndexMut};
+use std::slice;
+
+/// This type represents a color by its red, green and blue components.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Rgb {
+    /// The red component.
+    pub r: u8,
+    /// The green component.
+    pub g: u8,
+    /// The blue component.
+    pub b: u8,
+}
+
+impl Rgb {
+    /// Create a new color from its red, green and blue components.
+    pub fn new(r: u8, g: u8, b: u8) -> Rgb {
+        Rgb { r, g, b }
+    }
+
+    /// Create a color from a
+    /// [`COLORREF`](https://docs.microsoft.com/en-us/windows/win32/gdi/colorref).
+    ///
+    /// The high-order byte of a `COLORREF` is ignored.
+    pub fn from_colorref(colorref: u32) -> Rgb {
+        Rgb {
+            r: colorref as u8,
+            g: (colorref >> 8) as u8,
+            b: (colorref >> 16) as u8,
+        }
+    }
+
+    /// Convert this color into a
+    /// [`COLORREF`](https://docs.microsoft.com/en-us/windows/win32/gdi/colorref).
+    pub fn to_colorref(self) -> u32 {
+        self.r as u32 | (self.g as u32) << 8 | (self.b as u32) << 16
+    }
+}
+
+/// The 16 colors a console screen buffer can display.
+///
+/// The index of a color is the value used for it in the foreground (bits 0-3) and background
+/// (bits 4-7) of the [character
+/// attributes](https://docs.microsoft.com/en-us/windows/console/console-screen-buffers#character-attributes),
+/// so index 1 is what the console draws for `FOREGROUND_BLUE` and `BACKGROUND_BLUE`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct ColorTable([Rgb; ColorTable::LEN]);
+
+impl ColorTable {
+    /// The number of colors in a console color table.
+    pub const LEN: usize = 16;
+
+    /// Create a new color table from its colors.
+    pub fn new(colors: [Rgb; ColorTable::LEN]) -> ColorTable {
+        ColorTable(colors)
+    }
+
+    /// Create a color table from the `ColorTable` member of
+    /// [`CONSOLE_SCREEN_BUFFER_INFOEX`](https://docs.microsoft.com/en-us/windows/console/console-screen-buffer-infoex).
+    pub fn from_colorrefs(colorrefs: [u32; ColorTable::LEN]) -> ColorTable {
+        let mut colors = [Rgb::default(); ColorTable::LEN];
+        for (color, colorref) in colors.iter_mut().zip(colorrefs.iter()) {
+            *color = Rgb::from_colorref(*colorref);
+        }
+        ColorTable(colors)
+    }
+
+    /// Convert this color table into the `ColorTable` member of
+    /// [`CONSOLE_SCREEN_BUFFER_INFOEX`](https://docs.microsoft.com/en-us/windows/console/console-screen-buffer-infoex).
+    pub fn to_colorrefs(&self) -> [u32; ColorTable::LEN] {
+        let mut colorrefs = [0; ColorTable::LEN];
+        for (colorref, color) in colorrefs.iter_mut().zip(self.0.iter()) {
+            *colorref = color.to_colorref();
+        }
+        colorrefs
+    }
+
+    /// Get the color at the given index, or `None` if the index is not below [`ColorTable::LEN`].
+    pub fn get(&self, index: usize) -> Option<Rgb> {
+        self.0.get(index).copied()
+    }
+
+    /// Get all the colors in this table.
+    pub fn colors(&self) -> &[Rgb; ColorTable::LEN] {
+        &self.0
+    }
+
+    /// Iterate over the colors in this table, starting at index 0.
+    pub fn iter(&self) -> slice::Iter<'_, Rgb> {
+        self.0.iter()
+    }
+}
+
+impl Index<usize> for ColorTable {
+    type Output = Rgb;
+
+    fn index(&self, index: usize) -> &Rgb {
+        &self.0[index]
+    }
+}
+
+impl IndexMut<usize> for ColorTable {
+    fn index_mut(&mut self, index: usize) -> &mut Rgb {
+        &mut self.0[index]
+    }
+}
+
+impl From<[Rgb; ColorTable::LEN]> for ColorTable {
+    fn from(colors: [Rgb; ColorTable::LEN]) -> Self {
+        ColorTable(colors)
+    }
+}
+
+impl<'a> IntoIterator for &'a ColorTable {
+    type Item = &'a Rgb;
+    type IntoIter = slice::Iter<'a, Rgb>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ColorTable, Rgb};
+
+    #[test]
+    fn test_rgb_colorref_layout() {
+        let color = Rgb::new(0x12, 0x34, 0x56);
+
+        assert_eq!(color.to_colorref(), 0x0056_3412);
+        assert_eq!(Rgb::from_colorref(0x0056_3412), color);
+        // the high-order byte is not part of the color
+        assert_eq!(Rgb::from_colorref(0xFF56_3412), color);
+    }
+
+    #[test]
+    fn test_color_table_colorrefs_roundtrip() {
+        let mut colorrefs = [0; ColorTable::LEN];
+        for (i, colorref) in colorrefs.iter_mut().enumerate() {
+            *colorref = (i as u32) * 0x0001_0203;
+        }
+
+        let table = ColorTable::from_colorrefs(colorrefs);
+        assert_eq!(table[1], Rgb::new(0x03, 0x02, 0x01));
+        assert_eq!(table.get(ColorTable::LEN), None);
+        assert_eq!(table.to_colorrefs(), colorrefs);
+    }
+}
@@ -0,0 +1,40 @@
+//! This module provides the error for conversions from and to [`Coord`](super::Coord) and
+//! [`Size`](super::Size) that can fail.
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+
+/// The error returned when a value can not be converted into or from a [`Coord`](crate::Coord)
+/// or [`Size`](crate::Size) without losing information.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ConversionError {
+    /// A component is negative, but the target type can only hold non-negative values.
+    Negative,
+    /// A component is too large for the target type.
+    Overflow,
+    /// A position or index lies outside of the size it is mapped for.
+    OutOfBounds,
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConversionError::Negative => f.write_str("value is negative"),
+            ConversionError::Overflow => f.write_str("value is too large"),
+            ConversionError::OutOfBounds => f.write_str("position is out of bounds"),
+        }
+    }
+}
+
+impl Error for ConversionError {}
+
+/// Convert a component of a `Coord` or `Size` into an unsigned value.
+pub(crate) fn to_u16(value: i16) -> Result<u16, ConversionError> {
+    u16::try_from(value).map_err(|_| ConversionError::Negative)
+}
+
+/// Convert an unsigned value into a component of a `Coord` or `Size`.
+pub(crate) fn from_u16(value: u16) -> Result<i16, ConversionError> {
+    i16::try_from(value).map_err(|_| ConversionError::Overflow)
+}
@@ -2,10 +2,21 @@
 //! For example, in WinAPI we have `COORD` which looks and feels inconvenient.
 //! This module provides also some trait implementations who will make parsing and working with `COORD` easier.
 
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+
+#[cfg(windows)]
 use winapi::um::wincon::COORD;
 
+use super::conversion::{from_u16, to_u16, ConversionError};
+
 /// This is type represents the position of something on a certain 'x' and 'y'.
-#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, PartialOrd)]
+///
+/// Adding and subtracting coordinates works component-wise and, like for integers, panics on
+/// overflow in debug builds. Use the `checked_` or `saturating_` functions when the result may
+/// not fit.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, PartialOrd, Hash)]
 pub struct Coord {
     /// the position on the x axis
     pub x: i16,
@@ -18,14 +29,91 @@ impl Coord {
     pub fn new(x: i16, y: i16) -> Coord {
         Coord { x, y }
     }
+
+    /// Add two coordinates, returning `None` if either component overflows.
+    pub fn checked_add(self, rhs: Coord) -> Option<Coord> {
+        Some(Coord::new(
+            self.x.checked_add(rhs.x)?,
+            self.y.checked_add(rhs.y)?,
+        ))
+    }
+
+    /// Subtract two coordinates, returning `None` if either component overflows.
+    pub fn checked_sub(self, rhs: Coord) -> Option<Coord> {
+        Some(Coord::new(
+            self.x.checked_sub(rhs.x)?,
+            self.y.checked_sub(rhs.y)?,
+        ))
+    }
+
+    /// Add two coordinates, saturating each component at the bounds of `i16`.
+    pub fn saturating_add(self, rhs: Coord) -> Coord {
+        Coord::new(self.x.saturating_add(rhs.x), self.y.saturating_add(rhs.y))
+    }
+
+    /// Subtract two coordinates, saturating each component at the bounds of `i16`.
+    pub fn saturating_sub(self, rhs: Coord) -> Coord {
+        Coord::new(self.x.saturating_sub(rhs.x), self.y.saturating_sub(rhs.y))
+    }
+}
+
+impl Add for Coord {
+    type Output = Coord;
+
+    fn add(self, rhs: Coord) -> Coord {
+        Coord::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl AddAssign for Coord {
+    fn add_assign(&mut self, rhs: Coord) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Coord {
+    type Output = Coord;
+
+    fn sub(self, rhs: Coord) -> Coord {
+        Coord::new(self.x - rhs.x, self.y - rhs.y)
+    }
 }
 
+impl SubAssign for Coord {
+    fn sub_assign(&mut self, rhs: Coord) {
+        *self = *self - rhs;
+    }
+}
+
+impl fmt::Display for Coord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+impl From<(i16, i16)> for Coord {
+    fn from((x, y): (i16, i16)) -> Self {
+        Coord::new(x, y)
+    }
+}
+
+impl TryFrom<(u16, u16)> for Coord {
+    type Error = ConversionError;
+
+    /// Fails with [`ConversionError::Overflow`] if a component is larger than `i16::MAX`.
+    fn try_from((x, y): (u16, u16)) -> Result<Self, Self::Error> {
+        Ok(Coord::new(from_u16(x)?, from_u16(y)?))
+    }
+}
+
+#[cfg(windows)]
 impl From<COORD> for Coord {
     fn from(coord: COORD) -> Self {
         Coord::new(coord.X, coord.Y)
     }
 }
 
+#[cfg(windows)]
 impl From<Coord> for COORD {
     fn from(location: Coord) -> Self {
         COORD {
@@ -35,8 +123,45 @@ impl From<Coord> for COORD {
     }
 }
 
-impl Into<(u16, u16)> for Coord {
-    fn into(self) -> (u16, u16) {
-        (self.x as u16, self.y as u16)
+impl TryFrom<Coord> for (u16, u16) {
+    type Error = ConversionError;
+
+    /// Fails with [`ConversionError::Negative`] if a component is negative.
+    fn try_from(coord: Coord) -> Result<Self, Self::Error> {
+        Ok((to_u16(coord.x)?, to_u16(coord.y)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::Coord;
+    use crate::ConversionError;
+
+    #[test]
+    fn test_unsigned_conversions() {
+        assert_eq!(<(u16, u16)>::try_from(Coord::new(3, 4)), Ok((3, 4)));
+        assert_eq!(
+            <(u16, u16)>::try_from(Coord::new(3, -4)),
+            Err(ConversionError::Negative)
+        );
+        assert_eq!(
+            Coord::try_from((0u16, 0x8000u16)),
+            Err(ConversionError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(Coord::new(1, 2) + Coord::new(3, 4), Coord::new(4, 6));
+        assert_eq!(Coord::new(1, 2) - Coord::new(3, 4), Coord::new(-2, -2));
+        assert_eq!(Coord::new(i16::MAX, 0).checked_add(Coord::new(1, 0)), None);
+        assert_eq!(Coord::new(i16::MIN, 0).checked_sub(Coord::new(1, 0)), None);
+        assert_eq!(
+            Coord::new(i16::MAX, 0).saturating_add(Coord::new(1, 1)),
+            Coord::new(i16::MAX, 1)
+        );
+        assert_eq!(format!("{}", Coord::new(3, 4)), "(3, 4)");
     }
 }
@@ -1,9 +1,7 @@
 //! This module provides a few structs to wrap common input struts to a rusty interface
 //!
 //! Types like:
-//! - `KEY_EVENT_RECORD`
 //! - `MOUSE_EVENT_RECORD`
-//! - `ControlKeyState`
 //! - `ButtonState`
 //! - `EventFlags`
 //! - `InputEventType`
@@ -13,54 +11,13 @@ use winapi::shared::minwindef::DWORD;
 use winapi::um::wincon::{
     FOCUS_EVENT, FOCUS_EVENT_RECORD, FROM_LEFT_1ST_BUTTON_PRESSED, FROM_LEFT_2ND_BUTTON_PRESSED,
     FROM_LEFT_3RD_BUTTON_PRESSED, FROM_LEFT_4TH_BUTTON_PRESSED, INPUT_RECORD, KEY_EVENT,
-    KEY_EVENT_RECORD, MENU_EVENT, MENU_EVENT_RECORD, MOUSE_EVENT, MOUSE_EVENT_RECORD,
-    RIGHTMOST_BUTTON_PRESSED, WINDOW_BUFFER_SIZE_EVENT, WINDOW_BUFFER_SIZE_RECORD,
+    MENU_EVENT, MENU_EVENT_RECORD, MOUSE_EVENT, MOUSE_EVENT_RECORD, RIGHTMOST_BUTTON_PRESSED,
+    WINDOW_BUFFER_SIZE_EVENT, WINDOW_BUFFER_SIZE_RECORD,
 };
 
-use super::Coord;
+use super::{ControlKeyState, Coord, KeyEventRecord};
 use crate::ScreenBuffer;
 
-/// A [keyboard input event](https://docs.microsoft.com/en-us/windows/console/key-event-record-str).
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub struct KeyEventRecord {
-    /// If the key is pressed, this member is true. Otherwise, this member is
-    /// false (the key is released).
-    pub key_down: bool,
-    /// The repeat count, which indicates that a key is being held down.
-    /// For example, when a key is held down, you might get five events with
-    /// this member equal to 1, one event with this member equal to 5, or
-    /// multiple events with this member greater than or equal to 1.
-    pub repeat_count: u16,
-    /// A virtual-key code that identifies the given key in a
-    /// device-independent manner.
-    pub virtual_key_code: u16,
-    /// The virtual scan code of the given key that represents the
-    /// device-dependent value generated by the keyboard hardware.
-    pub virtual_scan_code: u16,
-    /// The translated Unicode character (as a WCHAR, or utf-16 value)
-    pub u_char: u16,
-    /// The state of the control keys.
-    pub control_key_state: ControlKeyState,
-}
-
-impl KeyEventRecord {
-    /// Convert a `KEY_EVENT_RECORD` to KeyEventRecord. This function is private
-    /// because the `KEY_EVENT_RECORD` has several union fields for characters
-    /// (u8 vs u16) that we always interpret as u16. We always use the wide
-    /// versions of windows API calls to support this.
-    #[inline]
-    fn from_winapi(record: &KEY_EVENT_RECORD) -> Self {
-        KeyEventRecord {
-            key_down: record.bKeyDown != 0,
-            repeat_count: record.wRepeatCount,
-            virtual_key_code: record.wVirtualKeyCode,
-            virtual_scan_code: record.wVirtualScanCode,
-            u_char: unsafe { *record.uChar.UnicodeChar() },
-            control_key_state: ControlKeyState(record.dwControlKeyState),
-        }
-    }
-}
-
 /// A [mouse input event](https://docs.microsoft.com/en-us/windows/console/mouse-event-record-str).
 #[derive(PartialEq, Debug, Copy, Clone, Eq)]
 pub struct MouseEvent {
@@ -80,7 +37,7 @@ impl From<MOUSE_EVENT_RECORD> for MouseEvent {
         MouseEvent {
             mouse_position: event.dwMousePosition.into(),
             button_state: event.dwButtonState.into(),
-            control_key_state: ControlKeyState(event.dwControlKeyState),
+            control_key_state: event.dwControlKeyState.into(),
             event_flags: event.dwEventFlags.into(),
         }
     }
@@ -180,31 +137,6 @@ impl ButtonState {
     }
 }
 
-/// The state of the control keys.
-///
-/// This is a bitmask of the following values.
-///
-/// | Description | Value |
-/// | --- | --- |
-/// | The right alt key is pressed | `0x0001` |
-/// | The left alt key is pressed | `x0002` |
-/// | The right control key is pressed | `0x0004` |
-/// | The left control key is pressed | `x0008` |
-/// | The shift key is pressed | `0x0010` |
-/// | The num lock light is on | `0x0020` |
-/// | The scroll lock light is on | `0x0040` |
-/// | The caps lock light is on | `0x0080` |
-/// | The key is [enhanced](https://docs.microsoft.com/en-us/windows/console/key-event-record-str#remarks) | `0x0100` |
-#[derive(PartialEq, Debug, Copy, Clone, Eq)]
-pub struct ControlKeyState(u32);
-
-impl ControlKeyState {
-    /// Whether the control key has a state.
-    pub fn has_state(&self, state: u32) -> bool {
-        (state & self.0) != 0
-    }
-}
-
 /// The type of mouse event.
 /// If this value is zero, it indicates a mouse button being pressed or released.
 /// Otherwise, this member is one of the following values.
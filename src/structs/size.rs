@@ -2,12 +2,25 @@
 //! For example, in WinAPI we have `COORD` to represent screen/buffer size but this is a little inconvenient.
 //! This module provides some trait implementations who will make parsing and working with `COORD` easier.
 
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+
+#[cfg(windows)]
 use winapi::um::wincon::COORD;
 
+use super::conversion::{from_u16, to_u16, ConversionError};
+use super::Coord;
+
 /// This is type represents the size of something in width and height.
-#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+///
+/// Adding and subtracting sizes works component-wise and, like for integers, panics on overflow
+/// in debug builds. Use the `checked_` or `saturating_` functions when the result may not fit.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
 pub struct Size {
+    /// The number of columns.
     pub width: i16,
+    /// The number of rows.
     pub height: i16,
 }
 
@@ -16,16 +29,215 @@ impl Size {
     pub fn new(width: i16, height: i16) -> Size {
         Size { width, height }
     }
+
+    /// Get the number of cells, or 0 if the width or height is negative.
+    pub fn area(self) -> usize {
+        if self.width < 0 || self.height < 0 {
+            0
+        } else {
+            self.width as usize * self.height as usize
+        }
+    }
+
+    /// Whether the given position lies within a buffer of this size, which spans from `(0, 0)`
+    /// to `(width - 1, height - 1)`.
+    pub fn contains(self, coord: Coord) -> bool {
+        0 <= coord.x && coord.x < self.width && 0 <= coord.y && coord.y < self.height
+    }
+
+    /// Get the row-major index of a cell in a buffer of this size, as used for a buffer of
+    /// `width * height` cells that stores one row after the other.
+    ///
+    /// Fails with [`ConversionError::OutOfBounds`] if the position does not lie within the size.
+    pub fn index_of(self, coord: Coord) -> Result<usize, ConversionError> {
+        if !self.contains(coord) {
+            return Err(ConversionError::OutOfBounds);
+        }
+        Ok(coord.y as usize * self.width as usize + coord.x as usize)
+    }
+
+    /// Get the position of the cell with the given row-major index in a buffer of this size.
+    ///
+    /// This is the inverse of [`Size::index_of`], and fails with
+    /// [`ConversionError::OutOfBounds`] if the index is not below [`Size::area`].
+    pub fn coord_of(self, index: usize) -> Result<Coord, ConversionError> {
+        if index >= self.area() {
+            return Err(ConversionError::OutOfBounds);
+        }
+        let width = self.width as usize;
+        Ok(Coord::new((index % width) as i16, (index / width) as i16))
+    }
+
+    /// Add two sizes, returning `None` if either component overflows.
+    pub fn checked_add(self, rhs: Size) -> Option<Size> {
+        Some(Size::new(
+            self.width.checked_add(rhs.width)?,
+            self.height.checked_add(rhs.height)?,
+        ))
+    }
+
+    /// Subtract two sizes, returning `None` if either component overflows.
+    pub fn checked_sub(self, rhs: Size) -> Option<Size> {
+        Some(Size::new(
+            self.width.checked_sub(rhs.width)?,
+            self.height.checked_sub(rhs.height)?,
+        ))
+    }
+
+    /// Add two sizes, saturating each component at the bounds of `i16`.
+    pub fn saturating_add(self, rhs: Size) -> Size {
+        Size::new(
+            self.width.saturating_add(rhs.width),
+            self.height.saturating_add(rhs.height),
+        )
+    }
+
+    /// Subtract two sizes, saturating each component at the bounds of `i16`.
+    pub fn saturating_sub(self, rhs: Size) -> Size {
+        Size::new(
+            self.width.saturating_sub(rhs.width),
+            self.height.saturating_sub(rhs.height),
+        )
+    }
+}
+
+impl Add for Size {
+    type Output = Size;
+
+    fn add(self, rhs: Size) -> Size {
+        Size::new(self.width + rhs.width, self.height + rhs.height)
+    }
+}
+
+impl AddAssign for Size {
+    fn add_assign(&mut self, rhs: Size) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Size {
+    type Output = Size;
+
+    fn sub(self, rhs: Size) -> Size {
+        Size::new(self.width - rhs.width, self.height - rhs.height)
+    }
+}
+
+impl SubAssign for Size {
+    fn sub_assign(&mut self, rhs: Size) {
+        *self = *self - rhs;
+    }
+}
+
+impl fmt::Display for Size {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+impl From<(i16, i16)> for Size {
+    fn from((width, height): (i16, i16)) -> Self {
+        Size::new(width, height)
+    }
 }
 
+impl TryFrom<(u16, u16)> for Size {
+    type Error = ConversionError;
+
+    /// Fails with [`ConversionError::Overflow`] if a component is larger than `i16::MAX`.
+    fn try_from((width, height): (u16, u16)) -> Result<Self, Self::Error> {
+        Ok(Size::new(from_u16(width)?, from_u16(height)?))
+    }
+}
+
+#[cfg(windows)]
 impl From<COORD> for Size {
     fn from(coord: COORD) -> Self {
         Size::new(coord.X, coord.Y)
     }
 }
 
-impl Into<(u16, u16)> for Size {
-    fn into(self) -> (u16, u16) {
-        (self.width as u16, self.height as u16)
+#[cfg(windows)]
+impl From<Size> for COORD {
+    fn from(size: Size) -> Self {
+        COORD {
+            X: size.width,
+            Y: size.height,
+        }
+    }
+}
+
+impl TryFrom<Size> for (u16, u16) {
+    type Error = ConversionError;
+
+    /// Fails with [`ConversionError::Negative`] if a component is negative.
+    fn try_from(size: Size) -> Result<Self, Self::Error> {
+        Ok((to_u16(size.width)?, to_u16(size.height)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use proptest::prelude::*;
+
+    use super::Size;
+    use crate::{ConversionError, Coord};
+
+    #[test]
+    fn test_unsigned_conversions() {
+        assert_eq!(<(u16, u16)>::try_from(Size::new(80, 25)), Ok((80, 25)));
+        assert_eq!(
+            <(u16, u16)>::try_from(Size::new(-1, 25)),
+            Err(ConversionError::Negative)
+        );
+        assert_eq!(Size::try_from((80u16, 25u16)), Ok(Size::new(80, 25)));
+        assert_eq!(
+            Size::try_from((0x8000u16, 0u16)),
+            Err(ConversionError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(Size::new(80, 25) - Size::new(1, 1), Size::new(79, 24));
+        assert_eq!(
+            Size::new(i16::MIN, 0).saturating_sub(Size::new(1, 1)),
+            Size::new(i16::MIN, -1)
+        );
+        assert_eq!(format!("{}", Size::new(80, 25)), "80x25");
+    }
+
+    #[test]
+    fn test_index_out_of_bounds() {
+        let size = Size::new(80, 25);
+
+        assert_eq!(size.index_of(Coord::new(79, 24)), Ok(1999));
+        assert_eq!(
+            size.index_of(Coord::new(80, 0)),
+            Err(ConversionError::OutOfBounds)
+        );
+        assert_eq!(
+            size.index_of(Coord::new(0, -1)),
+            Err(ConversionError::OutOfBounds)
+        );
+        assert_eq!(size.coord_of(2000), Err(ConversionError::OutOfBounds));
+        assert_eq!(
+            Size::new(0, 25).coord_of(0),
+            Err(ConversionError::OutOfBounds)
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_index_roundtrip(width in 1i16..300, height in 1i16..300, index in 0usize..90_000) {
+            let size = Size::new(width, height);
+            prop_assume!(index < size.area());
+
+            let coord = size.coord_of(index).unwrap();
+            prop_assert!(size.contains(coord));
+            prop_assert_eq!(size.index_of(coord), Ok(index));
+        }
     }
 }
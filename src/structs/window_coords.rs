@@ -2,9 +2,18 @@
 //! For example, in WinAPI we have `SMALL_RECT` to represent a window size but this is a little inconvenient.
 //! This module provides some trait implementations who will make parsing and working with `SMALL_RECT` easier.
 
+use std::cmp::{max, min};
+
+#[cfg(windows)]
 use winapi::um::wincon::{CONSOLE_SCREEN_BUFFER_INFO, SMALL_RECT};
 
+use super::{Coord, Size};
+
 /// This is a wrapper for the locations of a rectangle.
+///
+/// Like `SMALL_RECT`, all four edges are inclusive: a rectangle with `left` and `right` both set
+/// to 3 is one column wide. A rectangle whose `right` is smaller than its `left`, or whose
+/// `bottom` is smaller than its `top`, is empty.
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 pub struct WindowPositions {
     /// The rectangle's offset from the left.
@@ -17,12 +26,173 @@ pub struct WindowPositions {
     pub top: i16,
 }
 
+impl WindowPositions {
+    /// Create a rectangle from its upper-left corner and its size.
+    ///
+    /// A size with a width or height of zero or less results in an empty rectangle. Edges that
+    /// would lie outside of the range of an `i16` are clamped to it. Because the edges are
+    /// inclusive, an empty rectangle can't start at `i16::MIN`: it starts one cell later instead.
+    pub fn from_origin_size(origin: Coord, size: Size) -> WindowPositions {
+        let (left, right) = edges(origin.x, size.width);
+        let (top, bottom) = edges(origin.y, size.height);
+        WindowPositions {
+            left,
+            right,
+            bottom,
+            top,
+        }
+    }
+
+    /// Get the upper-left corner of the rectangle.
+    pub fn origin(&self) -> Coord {
+        Coord::new(self.left, self.top)
+    }
+
+    /// Get the number of columns in the rectangle, including both the left and right edge.
+    ///
+    /// This is zero for an empty rectangle, and saturates at `i16::MAX`.
+    pub fn width(&self) -> i16 {
+        clamp_i16(max(self.right as i32 - self.left as i32 + 1, 0))
+    }
+
+    /// Get the number of rows in the rectangle, including both the top and bottom edge.
+    ///
+    /// This is zero for an empty rectangle, and saturates at `i16::MAX`.
+    pub fn height(&self) -> i16 {
+        clamp_i16(max(self.bottom as i32 - self.top as i32 + 1, 0))
+    }
+
+    /// Get the width and height of the rectangle.
+    pub fn size(&self) -> Size {
+        Size::new(self.width(), self.height())
+    }
+
+    /// Whether the rectangle contains no cells at all.
+    pub fn is_empty(&self) -> bool {
+        self.right < self.left || self.bottom < self.top
+    }
+
+    /// Whether the given position lies within the rectangle.
+    pub fn contains(&self, coord: Coord) -> bool {
+        self.left <= coord.x
+            && coord.x <= self.right
+            && self.top <= coord.y
+            && coord.y <= self.bottom
+    }
+
+    /// Get the cells that lie in both this rectangle and `other`, or `None` if there are none.
+    pub fn intersection(&self, other: &WindowPositions) -> Option<WindowPositions> {
+        let intersection = WindowPositions {
+            left: max(self.left, other.left),
+            right: min(self.right, other.right),
+            top: max(self.top, other.top),
+            bottom: min(self.bottom, other.bottom),
+        };
+
+        if intersection.is_empty() {
+            None
+        } else {
+            Some(intersection)
+        }
+    }
+
+    /// Get the smallest rectangle that contains both this rectangle and `other`.
+    ///
+    /// Empty rectangles do not contribute to the result; the union of two empty rectangles is
+    /// `self`.
+    pub fn union(&self, other: &WindowPositions) -> WindowPositions {
+        if other.is_empty() {
+            return *self;
+        }
+        if self.is_empty() {
+            return *other;
+        }
+
+        WindowPositions {
+            left: min(self.left, other.left),
+            right: max(self.right, other.right),
+            top: min(self.top, other.top),
+            bottom: max(self.bottom, other.bottom),
+        }
+    }
+
+    /// Move the rectangle `dx` columns to the right and `dy` rows down.
+    ///
+    /// Returns `None` if any of the edges would overflow.
+    pub fn translate(&self, dx: i16, dy: i16) -> Option<WindowPositions> {
+        Some(WindowPositions {
+            left: self.left.checked_add(dx)?,
+            right: self.right.checked_add(dx)?,
+            top: self.top.checked_add(dy)?,
+            bottom: self.bottom.checked_add(dy)?,
+        })
+    }
+
+    /// Cut off the parts of the rectangle that lie outside of a buffer of the given size, which
+    /// spans from `(0, 0)` to `(size.width - 1, size.height - 1)`.
+    ///
+    /// Returns `None` if no part of the rectangle lies within the buffer.
+    pub fn clamp(&self, buffer_size: Size) -> Option<WindowPositions> {
+        self.intersection(&WindowPositions::from_origin_size(
+            Coord::default(),
+            buffer_size,
+        ))
+    }
+
+    /// Iterate over the rows of the rectangle, from top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = i16> {
+        // A rectangle with a width of zero has no rows, even if its height is not zero.
+        let (top, bottom) = if self.is_empty() {
+            (1, 0)
+        } else {
+            (self.top, self.bottom)
+        };
+        top..=bottom
+    }
+
+    /// Iterate over the positions of all cells in the rectangle, row by row from left to right.
+    pub fn cells(&self) -> impl Iterator<Item = Coord> {
+        let (left, right) = (self.left, self.right);
+        self.rows()
+            .flat_map(move |y| (left..=right).map(move |x| Coord::new(x, y)))
+    }
+}
+
+/// Get the first and last edge of `length` cells starting at `start`, with the last edge before
+/// the first one if `length` is zero or less.
+fn edges(start: i16, length: i16) -> (i16, i16) {
+    if length <= 0 {
+        let start = max(start, i16::MIN + 1);
+        (start, start - 1)
+    } else {
+        (start, clamp_i16(start as i32 + length as i32 - 1))
+    }
+}
+
+fn clamp_i16(value: i32) -> i16 {
+    min(max(value, i16::MIN as i32), i16::MAX as i32) as i16
+}
+
+impl From<(Coord, Size)> for WindowPositions {
+    fn from((origin, size): (Coord, Size)) -> Self {
+        WindowPositions::from_origin_size(origin, size)
+    }
+}
+
+impl From<WindowPositions> for (Coord, Size) {
+    fn from(positions: WindowPositions) -> Self {
+        (positions.origin(), positions.size())
+    }
+}
+
+#[cfg(windows)]
 impl From<CONSOLE_SCREEN_BUFFER_INFO> for WindowPositions {
     fn from(csbi: CONSOLE_SCREEN_BUFFER_INFO) -> Self {
         csbi.srWindow.into()
     }
 }
 
+#[cfg(windows)]
 impl From<WindowPositions> for SMALL_RECT {
     fn from(positions: WindowPositions) -> Self {
         SMALL_RECT {
@@ -34,6 +204,7 @@ impl From<WindowPositions> for SMALL_RECT {
     }
 }
 
+#[cfg(windows)]
 impl From<SMALL_RECT> for WindowPositions {
     fn from(rect: SMALL_RECT) -> Self {
         WindowPositions {
@@ -44,3 +215,148 @@ impl From<SMALL_RECT> for WindowPositions {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::WindowPositions;
+    use crate::{Coord, Size};
+
+    fn coord() -> impl Strategy<Value = Coord> {
+        (-200i16..200, -200i16..200).prop_map(|(x, y)| Coord::new(x, y))
+    }
+
+    /// Like `coord`, but also at the smallest edge, where an empty rectangle can't start.
+    fn origin() -> impl Strategy<Value = Coord> {
+        let component = || prop_oneof![Just(i16::MIN), -200i16..200];
+        (component(), component()).prop_map(|(x, y)| Coord::new(x, y))
+    }
+
+    fn size() -> impl Strategy<Value = Size> {
+        (0i16..50, 0i16..50).prop_map(|(width, height)| Size::new(width, height))
+    }
+
+    fn rect() -> impl Strategy<Value = WindowPositions> {
+        (coord(), size()).prop_map(WindowPositions::from)
+    }
+
+    #[test]
+    fn test_inclusive_edges() {
+        let rect = WindowPositions {
+            left: 2,
+            right: 4,
+            bottom: 1,
+            top: 1,
+        };
+
+        assert_eq!(rect.size(), Size::new(3, 1));
+        assert!(rect.contains(Coord::new(4, 1)));
+        assert!(!rect.contains(Coord::new(5, 1)));
+        assert_eq!(
+            rect.cells().collect::<Vec<_>>(),
+            vec![Coord::new(2, 1), Coord::new(3, 1), Coord::new(4, 1)]
+        );
+    }
+
+    #[test]
+    fn test_empty_at_smallest_edge() {
+        let rect =
+            WindowPositions::from_origin_size(Coord::new(i16::MIN, i16::MIN), Size::new(0, 3));
+
+        assert!(rect.is_empty());
+        assert_eq!(rect.size(), Size::new(0, 3));
+        assert_eq!(rect.origin(), Coord::new(i16::MIN + 1, i16::MIN));
+        assert!(!rect.contains(Coord::new(i16::MIN, i16::MIN)));
+    }
+
+    #[test]
+    fn test_extreme_edges_saturate() {
+        let rect = WindowPositions {
+            left: i16::MIN,
+            right: i16::MAX,
+            bottom: 0,
+            top: 0,
+        };
+
+        assert_eq!(rect.width(), i16::MAX);
+        assert_eq!(rect.translate(1, 0), None);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_origin_size_roundtrip(origin in origin(), size in size()) {
+            let rect = WindowPositions::from((origin, size));
+
+            prop_assert_eq!(rect.size(), size);
+            prop_assert_eq!(rect.is_empty(), size.width == 0 || size.height == 0);
+            prop_assert_eq!(rect.cells().count(), size.area());
+
+            let starts_at_min = (size.width == 0 && origin.x == i16::MIN)
+                || (size.height == 0 && origin.y == i16::MIN);
+            if !starts_at_min {
+                prop_assert_eq!(rect.origin(), origin);
+                prop_assert_eq!(<(Coord, Size)>::from(rect), (origin, size));
+            }
+        }
+
+        #[test]
+        fn prop_cells_match_contains(rect in rect(), point in coord()) {
+            let cells: Vec<Coord> = rect.cells().collect();
+
+            prop_assert_eq!(cells.len(), rect.width() as usize * rect.height() as usize);
+            prop_assert_eq!(cells.contains(&point), rect.contains(point));
+            prop_assert_eq!(rect.rows().count(), if rect.is_empty() { 0 } else { rect.height() as usize });
+        }
+
+        #[test]
+        fn prop_intersection_contains_common_cells(a in rect(), b in rect(), point in coord()) {
+            let in_both = a.contains(point) && b.contains(point);
+
+            match a.intersection(&b) {
+                Some(intersection) => {
+                    prop_assert!(!intersection.is_empty());
+                    prop_assert_eq!(intersection.contains(point), in_both);
+                    prop_assert_eq!(Some(intersection), b.intersection(&a));
+                }
+                None => prop_assert!(!in_both),
+            }
+        }
+
+        #[test]
+        fn prop_union_contains_both(a in rect(), b in rect(), point in coord()) {
+            let union = a.union(&b);
+
+            if a.contains(point) || b.contains(point) {
+                prop_assert!(union.contains(point));
+            }
+            prop_assert_eq!(union.is_empty(), a.is_empty() && b.is_empty());
+        }
+
+        #[test]
+        fn prop_translate_moves_cells(rect in rect(), point in coord(), dx in -100i16..100, dy in -100i16..100) {
+            let moved = rect.translate(dx, dy).unwrap();
+            let moved_point = Coord::new(point.x + dx, point.y + dy);
+
+            prop_assert_eq!(moved.size(), rect.size());
+            prop_assert_eq!(moved.contains(moved_point), rect.contains(point));
+        }
+
+        #[test]
+        fn prop_clamp_stays_in_buffer(rect in rect(), buffer in size(), point in coord()) {
+            let buffer_rect = WindowPositions::from((Coord::default(), buffer));
+
+            match rect.clamp(buffer) {
+                Some(clamped) => {
+                    prop_assert!(clamped.left >= 0 && clamped.top >= 0);
+                    prop_assert!(clamped.right < buffer.width && clamped.bottom < buffer.height);
+                    prop_assert_eq!(
+                        clamped.contains(point),
+                        rect.contains(point) && buffer_rect.contains(point)
+                    );
+                }
+                None => prop_assert_eq!(rect.intersection(&buffer_rect), None),
+            }
+        }
+    }
+}
@@ -0,0 +1,326 @@
+//! This module contains functions to get and set the title of the console window, and
+//! [`TitleGuard`], which restores the original title on drop.
+//!
+//! The functions that get a title copy it into a buffer of a fixed size. If the title doesn't
+//! fit, `GetConsoleTitleW` cuts it off, while `GetConsoleOriginalTitleW` copies nothing and
+//! returns zero, just like for an empty title. Growing the buffer until the title fits is plain
+//! logic, which takes the WinAPI function as a parameter so it can be tested anywhere.
+
+#[cfg(windows)]
+use std::io;
+
+#[cfg(windows)]
+use winapi::um::{
+    errhandlingapi::SetLastError,
+    wincon::{GetConsoleOriginalTitleW, GetConsoleTitleW, SetConsoleTitleW},
+};
+
+#[cfg(windows)]
+use crate::handle::report_close_error;
+#[cfg(windows)]
+use crate::{result, wide_nul};
+#[cfg(any(windows, test))]
+use crate::{Error, ErrorKind, Result};
+
+/// The number of UTF-16 code units of the first buffer a title is read into.
+#[cfg(any(windows, test))]
+const INITIAL_CAPACITY: usize = 256;
+
+/// The number of UTF-16 code units of the largest buffer a title is read into, which is far more
+/// than the console keeps.
+#[cfg(any(windows, test))]
+const MAXIMUM_CAPACITY: usize = 1 << 20;
+
+/// Read a title with the WinAPI function `api`, growing the buffer until the title fits.
+///
+/// `get` wraps `api`: it copies the title into the buffer, including a NUL character, and
+/// returns its length, or `None` if the function returned zero without an error.
+///
+/// - A function that cuts the title off returns the length of the whole title or of the part that
+///   was copied, depending on the version of Windows, so the title only fits for sure when it
+///   leaves room in the buffer.
+/// - A function that returns `None` copied nothing, because the title is empty or the buffer is
+///   too small. The buffer grows until the title fits, and the title is only taken to be empty
+///   when it doesn't fit the largest buffer either.
+///
+/// Unpaired surrogates in the title are replaced with `U+FFFD`.
+#[cfg(any(windows, test))]
+fn read_title<F>(api: &'static str, mut get: F) -> Result<String>
+where
+    F: FnMut(&mut [u16]) -> Result<Option<usize>>,
+{
+    let mut capacity = INITIAL_CAPACITY;
+    loop {
+        let mut buffer = vec![0; capacity];
+        let length = get(&mut buffer)?;
+        match length {
+            Some(length) if length + 1 < capacity => {
+                return Ok(String::from_utf16_lossy(&buffer[..length]));
+            }
+            None if capacity >= MAXIMUM_CAPACITY => return Ok(String::new()),
+            _ => {}
+        }
+
+        if capacity >= MAXIMUM_CAPACITY {
+            return Err(Error::new(
+                api,
+                ErrorKind::InvalidData,
+                format!(
+                    "title is longer than {} UTF-16 code units",
+                    MAXIMUM_CAPACITY
+                ),
+            ));
+        }
+        // Go straight to the whole length if the function reported it.
+        capacity = (capacity * 2)
+            .max(length.map_or(0, |length| length + 2))
+            .min(MAXIMUM_CAPACITY);
+    }
+}
+
+/// Call `GetConsoleTitleW` or `GetConsoleOriginalTitleW` as `api` with the buffer.
+///
+/// Both return zero on failure, but also for an empty title, and `GetConsoleOriginalTitleW` for
+/// a buffer that is too small, which can only be told apart from failure by the last error. Zero
+/// without an error results in `None`.
+#[cfg(windows)]
+fn get_title(
+    api: &'static str,
+    function: unsafe extern "system" fn(*mut u16, u32) -> u32,
+    buffer: &mut [u16],
+) -> Result<Option<usize>> {
+    let length = unsafe {
+        SetLastError(0);
+        function(buffer.as_mut_ptr(), buffer.len() as u32)
+    };
+    if length != 0 {
+        return Ok(Some(length as usize));
+    }
+    match io::Error::last_os_error().raw_os_error() {
+        Some(0) | None => Ok(None),
+        Some(code) => Err(Error::from_code(api, code as u32)),
+    }
+}
+
+/// Get the title of the console window.
+///
+/// This wraps
+/// [`GetConsoleTitleW`](https://docs.microsoft.com/en-us/windows/console/getconsoletitle).
+#[cfg(windows)]
+pub fn title() -> Result<String> {
+    // `GetConsoleTitleW` cuts off a title that doesn't fit, so zero means the title is empty.
+    read_title("GetConsoleTitleW", |buffer| {
+        get_title("GetConsoleTitleW", GetConsoleTitleW, buffer).map(|length| length.or(Some(0)))
+    })
+}
+
+/// Get the title the console window had when the console was created.
+///
+/// This wraps
+/// [`GetConsoleOriginalTitleW`](https://docs.microsoft.com/en-us/windows/console/getconsoleoriginaltitle).
+#[cfg(windows)]
+pub fn original_title() -> Result<String> {
+    read_title("GetConsoleOriginalTitleW", |buffer| {
+        get_title("GetConsoleOriginalTitleW", GetConsoleOriginalTitleW, buffer)
+    })
+}
+
+/// Set the title of the console window.
+///
+/// This wraps
+/// [`SetConsoleTitleW`](https://docs.microsoft.com/en-us/windows/console/setconsoletitle).
+#[cfg(windows)]
+pub fn set_title(title: &str) -> Result<()> {
+    let wide = wide_nul("SetConsoleTitleW", title)?;
+    result("SetConsoleTitleW", unsafe {
+        SetConsoleTitleW(wide.as_ptr())
+    })
+    .map_err(|e| e.with_argument("title", &title))
+}
+
+/// Restores the title the console window had when the console was created, when it is dropped.
+///
+/// Errors of restoring the title while it is dropped are passed to the hook set with
+/// [`set_close_error_hook`](crate::set_close_error_hook).
+#[cfg(windows)]
+#[derive(Debug)]
+#[must_use = "the title is restored when the `TitleGuard` is dropped"]
+pub struct TitleGuard {
+    original: String,
+}
+
+#[cfg(windows)]
+impl TitleGuard {
+    /// Remember the original title of the console window, to restore it on drop.
+    ///
+    /// This wraps
+    /// [`GetConsoleOriginalTitleW`](https://docs.microsoft.com/en-us/windows/console/getconsoleoriginaltitle).
+    pub fn new() -> Result<TitleGuard> {
+        Ok(TitleGuard {
+            original: original_title()?,
+        })
+    }
+
+    /// Get the title that is restored on drop.
+    pub fn original(&self) -> &str {
+        &self.original
+    }
+}
+
+#[cfg(windows)]
+impl Drop for TitleGuard {
+    fn drop(&mut self) {
+        if let Err(error) = set_title(&self.original) {
+            report_close_error(&error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_title, INITIAL_CAPACITY, MAXIMUM_CAPACITY};
+    use crate::{Error, ErrorKind};
+
+    /// What a fake function reports for a title that doesn't fit the buffer.
+    #[derive(Copy, Clone)]
+    enum Reports {
+        /// Cut the title off and return the length of the whole title.
+        WholeLength,
+        /// Cut the title off and return the length of the copied part.
+        CopiedLength,
+        /// Copy nothing and return zero, like `GetConsoleOriginalTitleW`, which it also does for
+        /// an empty title.
+        Zero,
+    }
+
+    /// A fake `GetConsoleTitleW` or `GetConsoleOriginalTitleW` for `title`.
+    fn fake_get_title<'a>(
+        title: &'a [u16],
+        reports: Reports,
+        capacities: &'a mut Vec<usize>,
+    ) -> impl FnMut(&mut [u16]) -> crate::Result<Option<usize>> + 'a {
+        move |buffer| {
+            capacities.push(buffer.len());
+            if let Reports::Zero = reports {
+                if title.is_empty() || title.len() >= buffer.len() {
+                    return Ok(None);
+                }
+            }
+            let copied = title.len().min(buffer.len() - 1);
+            buffer[..copied].copy_from_slice(&title[..copied]);
+            buffer[copied] = 0;
+            Ok(Some(match reports {
+                Reports::WholeLength => title.len(),
+                Reports::CopiedLength | Reports::Zero => copied,
+            }))
+        }
+    }
+
+    #[test]
+    fn test_short_title() {
+        let title: Vec<u16> = "crossterm — ✓".encode_utf16().collect();
+        let mut capacities = Vec::new();
+        let read = read_title(
+            "GetConsoleTitleW",
+            fake_get_title(&title, Reports::WholeLength, &mut capacities),
+        );
+        assert_eq!(read.unwrap(), "crossterm — ✓");
+        assert_eq!(capacities, vec![INITIAL_CAPACITY]);
+    }
+
+    #[test]
+    fn test_empty_title() {
+        let mut capacities = Vec::new();
+        let read = read_title(
+            "GetConsoleTitleW",
+            fake_get_title(&[], Reports::CopiedLength, &mut capacities),
+        );
+        assert_eq!(read.unwrap(), "");
+        assert_eq!(capacities, vec![INITIAL_CAPACITY]);
+
+        // Zero could also mean the buffer is too small, until even the largest one is.
+        let mut capacities = Vec::new();
+        let read = read_title(
+            "GetConsoleOriginalTitleW",
+            fake_get_title(&[], Reports::Zero, &mut capacities),
+        );
+        assert_eq!(read.unwrap(), "");
+        assert_eq!(capacities.last(), Some(&MAXIMUM_CAPACITY));
+    }
+
+    #[test]
+    fn test_long_title() {
+        let long = "🦀".repeat(1000);
+        let title: Vec<u16> = long.encode_utf16().collect();
+
+        // The whole length is reported, so the second buffer fits.
+        let mut capacities = Vec::new();
+        let read = read_title(
+            "GetConsoleTitleW",
+            fake_get_title(&title, Reports::WholeLength, &mut capacities),
+        );
+        assert_eq!(read.unwrap(), long);
+        assert_eq!(capacities, vec![INITIAL_CAPACITY, title.len() + 2]);
+
+        // Only the copied length is reported, so the buffer doubles until it fits.
+        let mut capacities = Vec::new();
+        let read = read_title(
+            "GetConsoleTitleW",
+            fake_get_title(&title, Reports::CopiedLength, &mut capacities),
+        );
+        assert_eq!(read.unwrap(), long);
+        assert_eq!(capacities, vec![256, 512, 1024, 2048]);
+
+        // Nothing is copied into a buffer that is too small, so it doubles until it fits.
+        let mut capacities = Vec::new();
+        let read = read_title(
+            "GetConsoleOriginalTitleW",
+            fake_get_title(&title, Reports::Zero, &mut capacities),
+        );
+        assert_eq!(read.unwrap(), long);
+        assert_eq!(capacities, vec![256, 512, 1024, 2048]);
+    }
+
+    #[test]
+    fn test_exact_fit() {
+        // A title that fills the buffer but for the NUL character could have been cut off.
+        let title = vec![u16::from(b'x'); INITIAL_CAPACITY - 1];
+        let mut capacities = Vec::new();
+        let read = read_title(
+            "GetConsoleTitleW",
+            fake_get_title(&title, Reports::CopiedLength, &mut capacities),
+        );
+        assert_eq!(read.unwrap().len(), INITIAL_CAPACITY - 1);
+        assert_eq!(capacities, vec![INITIAL_CAPACITY, 2 * INITIAL_CAPACITY]);
+    }
+
+    #[test]
+    fn test_unpaired_surrogate() {
+        let title = [u16::from(b'a'), 0xd800, u16::from(b'b')];
+        let mut capacities = Vec::new();
+        let read = read_title(
+            "GetConsoleTitleW",
+            fake_get_title(&title, Reports::WholeLength, &mut capacities),
+        );
+        assert_eq!(read.unwrap(), "a\u{fffd}b");
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = read_title("GetConsoleTitleW", |_| {
+            Err(Error::from_code("GetConsoleTitleW", 6))
+        })
+        .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidHandle);
+
+        // A function that never leaves room in the buffer stops at the maximum capacity.
+        let mut capacities = Vec::new();
+        let error = read_title("GetConsoleTitleW", |buffer| {
+            capacities.push(buffer.len());
+            Ok(Some(buffer.len() - 1))
+        })
+        .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert_eq!(capacities.last(), Some(&MAXIMUM_CAPACITY));
+    }
+}
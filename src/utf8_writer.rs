@@ -0,0 +1,360 @@
+//! This module contains a writer that turns a stream of UTF-8 bytes into UTF-16 for the wide
+//! console APIs.
+//!
+//! The bytes passed to [`io::Write::write`] don't have to end on a character boundary: an
+//! incomplete sequence at the end is kept until the next call completes it.
+
+use std::io;
+use std::str;
+
+/// A destination for UTF-16 text, like [`Console`](crate::Console).
+pub trait WriteUtf16 {
+    /// Write some UTF-16 code units, returning how many of them were written.
+    fn write_utf16(&mut self, units: &[u16]) -> io::Result<usize>;
+}
+
+impl<W: WriteUtf16 + ?Sized> WriteUtf16 for &mut W {
+    fn write_utf16(&mut self, units: &[u16]) -> io::Result<usize> {
+        (**self).write_utf16(units)
+    }
+}
+
+/// An [`io::Write`] implementation that converts UTF-8 to UTF-16.
+///
+/// A multi-byte character that is split across two calls to `write` is kept in the writer
+/// until the rest of it arrives; [`flush`](io::Write::flush) does not write such an incomplete
+/// character either. When fewer UTF-16 code units are written than were passed to
+/// [`WriteUtf16::write_utf16`], `write` returns the number of bytes of the characters that were
+/// written completely.
+///
+/// By default, invalid UTF-8 results in an error of kind
+/// [`InvalidData`](io::ErrorKind::InvalidData). With
+/// [`replace_invalid`](Utf8Writer::replace_invalid), invalid sequences are written as
+/// U+FFFD REPLACEMENT CHARACTER instead.
+#[derive(Debug)]
+pub struct Utf8Writer<W> {
+    inner: W,
+    tail: Vec<u8>,
+    replace_invalid: bool,
+}
+
+impl<W: WriteUtf16> Utf8Writer<W> {
+    /// Create a writer that writes to `inner`.
+    pub fn new(inner: W) -> Utf8Writer<W> {
+        Utf8Writer {
+            inner,
+            tail: Vec::new(),
+            replace_invalid: false,
+        }
+    }
+
+    /// Set whether invalid UTF-8 is replaced with U+FFFD instead of resulting in an error.
+    pub fn replace_invalid(mut self, replace_invalid: bool) -> Utf8Writer<W> {
+        self.replace_invalid = replace_invalid;
+        self
+    }
+
+    /// Get the bytes of an incomplete character that are waiting for the rest of the character.
+    pub fn pending(&self) -> &[u8] {
+        &self.tail
+    }
+
+    /// Get a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Unwrap this writer, discarding any incomplete character.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: WriteUtf16> io::Write for Utf8Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        // The pending bytes were already reported as written by a previous call.
+        let pending = self.tail.len();
+        let mut bytes = Vec::with_capacity(pending + buf.len());
+        bytes.extend_from_slice(&self.tail);
+        bytes.extend_from_slice(buf);
+
+        let encoded = match Encoded::new(&bytes, self.replace_invalid) {
+            Ok(encoded) => encoded,
+            Err(e) => {
+                self.tail.clear();
+                return Err(e);
+            }
+        };
+
+        let mut written = if encoded.units.is_empty() {
+            0
+        } else {
+            self.inner.write_utf16(&encoded.units)?
+        };
+
+        // Don't leave half of a surrogate pair behind.
+        let split_char = encoded
+            .boundaries
+            .iter()
+            .map(|&(units, _)| units)
+            .find(|&units| units > written);
+        if let Some(char_end) = split_char {
+            if written > encoded.units_before(char_end) {
+                while written < char_end {
+                    match self.inner.write_utf16(&encoded.units[written..char_end])? {
+                        0 => return Err(io::ErrorKind::WriteZero.into()),
+                        n => written += n,
+                    }
+                }
+            }
+        }
+
+        let mut consumed = encoded.bytes_for_units(written);
+        if written == encoded.units.len() {
+            if let Some(start) = encoded.incomplete {
+                self.tail = bytes[start..].to_vec();
+                consumed = bytes.len();
+            }
+        }
+
+        if consumed < pending {
+            // Not even the character that completes the pending bytes was written.
+            return Ok(0);
+        }
+        if consumed < bytes.len() || encoded.incomplete.is_none() {
+            self.tail.clear();
+        }
+
+        Ok(consumed - pending)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The UTF-16 encoding of a run of UTF-8, remembering where each character ends in both.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Encoded {
+    pub(crate) units: Vec<u16>,
+    /// For every character, the number of code units and bytes up to and including it.
+    pub(crate) boundaries: Vec<(usize, usize)>,
+    /// The start of an incomplete sequence at the end of the input.
+    pub(crate) incomplete: Option<usize>,
+}
+
+impl Encoded {
+    /// Encode as much of `bytes` as possible.
+    ///
+    /// Encoding stops at the first invalid sequence, unless `replace_invalid` is set, in which
+    /// case every invalid sequence is encoded as U+FFFD. An invalid sequence at the very start
+    /// is an error.
+    pub(crate) fn new(bytes: &[u8], replace_invalid: bool) -> io::Result<Encoded> {
+        let mut encoded = Encoded::default();
+        let mut pos = 0;
+
+        while pos < bytes.len() {
+            let (valid, error) = match str::from_utf8(&bytes[pos..]) {
+                Ok(valid) => (valid, None),
+                Err(e) => (
+                    str::from_utf8(&bytes[pos..pos + e.valid_up_to()]).unwrap(),
+                    Some(e),
+                ),
+            };
+
+            for c in valid.chars() {
+                pos += c.len_utf8();
+                encoded.push(c, pos);
+            }
+
+            match error.map(|e| e.error_len()) {
+                None => break,
+                Some(None) => {
+                    encoded.incomplete = Some(pos);
+                    break;
+                }
+                Some(Some(len)) if replace_invalid => {
+                    pos += len;
+                    encoded.push(char::REPLACEMENT_CHARACTER, pos);
+                }
+                Some(Some(_)) if encoded.units.is_empty() => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "stream did not contain valid UTF-8",
+                    ));
+                }
+                Some(Some(_)) => break,
+            }
+        }
+
+        Ok(encoded)
+    }
+
+    fn push(&mut self, c: char, bytes_end: usize) {
+        let mut buf = [0; 2];
+        self.units.extend_from_slice(c.encode_utf16(&mut buf));
+        self.boundaries.push((self.units.len(), bytes_end));
+    }
+
+    /// Get the number of units of all characters before the one that ends at `units`.
+    fn units_before(&self, units: usize) -> usize {
+        self.boundaries
+            .iter()
+            .map(|&(end, _)| end)
+            .take_while(|&end| end < units)
+            .last()
+            .unwrap_or(0)
+    }
+
+    /// Get the number of bytes of the characters that are completely contained in the first
+    /// `units` code units.
+    pub(crate) fn bytes_for_units(&self, units: usize) -> usize {
+        self.boundaries
+            .iter()
+            .take_while(|&&(end, _)| end <= units)
+            .last()
+            .map_or(0, |&(_, bytes)| bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Write};
+
+    use proptest::prelude::*;
+
+    use super::{Encoded, Utf8Writer, WriteUtf16};
+
+    /// A sink that accepts at most `limit` code units per call.
+    #[derive(Default)]
+    struct FakeConsole {
+        written: Vec<u16>,
+        limit: Option<usize>,
+    }
+
+    impl WriteUtf16 for FakeConsole {
+        fn write_utf16(&mut self, units: &[u16]) -> io::Result<usize> {
+            let n = self
+                .limit
+                .map_or(units.len(), |limit| limit.min(units.len()));
+            self.written.extend_from_slice(&units[..n]);
+            Ok(n)
+        }
+    }
+
+    fn utf16(s: &str) -> Vec<u16> {
+        s.encode_utf16().collect()
+    }
+
+    #[test]
+    fn test_encode_boundaries() {
+        let encoded = Encoded::new("a€😀".as_bytes(), false).unwrap();
+
+        assert_eq!(encoded.units, utf16("a€😀"));
+        assert_eq!(encoded.boundaries, vec![(1, 1), (2, 4), (4, 8)]);
+        assert_eq!(encoded.bytes_for_units(3), 4);
+        assert_eq!(encoded.incomplete, None);
+    }
+
+    #[test]
+    fn test_encode_incomplete_and_invalid() {
+        let encoded = Encoded::new(b"ab\xE2\x82", false).unwrap();
+        assert_eq!(encoded.units, utf16("ab"));
+        assert_eq!(encoded.incomplete, Some(2));
+
+        let encoded = Encoded::new(b"ab\xFFcd", false).unwrap();
+        assert_eq!(encoded.units, utf16("ab"));
+        assert_eq!(encoded.incomplete, None);
+
+        let encoded = Encoded::new(b"ab\xFFcd\xE2", true).unwrap();
+        assert_eq!(encoded.units, utf16("ab\u{FFFD}cd"));
+        assert_eq!(encoded.boundaries[2], (3, 3));
+        assert_eq!(encoded.incomplete, Some(5));
+
+        let error = Encoded::new(b"\xFFcd", false).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_split_character() {
+        let mut writer = Utf8Writer::new(FakeConsole::default());
+        let euro = "€".as_bytes();
+
+        assert_eq!(writer.write(&[b'a', euro[0]]).unwrap(), 2);
+        assert_eq!(writer.pending(), &euro[..1]);
+        assert_eq!(writer.write(&euro[1..2]).unwrap(), 1);
+        assert_eq!(writer.write(&euro[2..]).unwrap(), 1);
+        assert_eq!(writer.pending(), b"");
+        assert_eq!(writer.get_ref().written, utf16("a€"));
+    }
+
+    #[test]
+    fn test_partial_write_reports_written_bytes() {
+        let mut writer = Utf8Writer::new(FakeConsole {
+            limit: Some(2),
+            ..FakeConsole::default()
+        });
+
+        // "é" is two bytes but one code unit
+        assert_eq!(writer.write("éé€".as_bytes()).unwrap(), 4);
+        assert_eq!(writer.write("€".as_bytes()).unwrap(), 3);
+        assert_eq!(writer.get_ref().written, utf16("éé€"));
+    }
+
+    #[test]
+    fn test_partial_write_finishes_surrogate_pair() {
+        let mut writer = Utf8Writer::new(FakeConsole {
+            limit: Some(1),
+            ..FakeConsole::default()
+        });
+
+        assert_eq!(writer.write("😀a".as_bytes()).unwrap(), 4);
+        assert_eq!(writer.get_ref().written, utf16("😀"));
+    }
+
+    #[test]
+    fn test_invalid_data() {
+        let mut writer = Utf8Writer::new(FakeConsole::default());
+        assert_eq!(writer.write(b"ab\xFFcd").unwrap(), 2);
+        assert_eq!(
+            writer.write(b"\xFFcd").unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        // an incomplete sequence that turns out to be invalid is dropped
+        writer.write_all(b"\xE2").unwrap();
+        assert!(writer.write(b"cd").is_err());
+        assert_eq!(writer.pending(), b"");
+
+        let mut writer = Utf8Writer::new(FakeConsole::default()).replace_invalid(true);
+        writer.write_all(b"ab\xFFcd\xE2").unwrap();
+        writer.write_all(b"cd").unwrap();
+        assert_eq!(writer.into_inner().written, utf16("ab\u{FFFD}cd\u{FFFD}cd"));
+    }
+
+    proptest! {
+        #[test]
+        fn prop_chunked_writes_roundtrip(
+            text in "\\PC{0,40}",
+            chunk in 1usize..8,
+            limit in 1usize..8,
+        ) {
+            let mut writer = Utf8Writer::new(FakeConsole {
+                limit: Some(limit),
+                ..FakeConsole::default()
+            });
+
+            for bytes in text.as_bytes().chunks(chunk) {
+                writer.write_all(bytes).unwrap();
+            }
+            writer.flush().unwrap();
+
+            prop_assert_eq!(writer.pending(), b"");
+            prop_assert_eq!(writer.into_inner().written, utf16(&text));
+        }
+    }
+}
@@ -0,0 +1,230 @@
+//! This module contains the logic for interpreting the results of the WinAPI wait functions,
+//! like
+//! [`WaitForSingleObject`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-waitforsingleobject)
+//! and
+//! [`WaitForMultipleObjects`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-waitformultipleobjects).
+
+use std::time::Duration;
+
+#[cfg(windows)]
+use winapi::shared::minwindef::BOOL;
+#[cfg(windows)]
+use winapi::um::synchapi::{WaitForMultipleObjects, WaitForSingleObject};
+#[cfg(windows)]
+use winapi::um::winnt::{HANDLE, MAXIMUM_WAIT_OBJECTS};
+
+#[cfg(windows)]
+use super::convention::last_error;
+use super::win32::{INFINITE, WAIT_ABANDONED_0, WAIT_FAILED, WAIT_OBJECT_0, WAIT_TIMEOUT};
+#[cfg(windows)]
+use super::Handle;
+use super::{Error, ErrorKind, Result};
+
+/// What ended a wait for one or more objects.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum WaitResult {
+    /// The object at this index in the waited for objects is signalled.
+    Signalled(usize),
+    /// The object at this index is a mutex whose owning thread exited without releasing it. The
+    /// calling thread now owns the mutex.
+    Abandoned(usize),
+    /// The timeout elapsed before any object was signalled.
+    TimedOut,
+}
+
+/// Get the result of a call to the WinAPI wait function `api` for `count` objects.
+///
+/// `WAIT_FAILED` results in an error with the Win32 error code returned by `last_error`, which is
+/// not called otherwise, and values that don't belong to any of the objects result in an error of
+/// kind [`Other`](ErrorKind::Other).
+pub fn wait_result(
+    api: &'static str,
+    return_value: u32,
+    count: usize,
+    last_error: impl FnOnce() -> u32,
+) -> Result<WaitResult> {
+    let index = |base: u32| {
+        return_value
+            .checked_sub(base)
+            .map(|index| index as usize)
+            .filter(|&index| index < count)
+    };
+
+    if return_value == WAIT_FAILED {
+        Err(Error::from_code(api, last_error()))
+    } else if return_value == WAIT_TIMEOUT {
+        Ok(WaitResult::TimedOut)
+    } else if let Some(index) = index(WAIT_OBJECT_0).filter(|_| return_value < WAIT_ABANDONED_0) {
+        Ok(WaitResult::Signalled(index))
+    } else if let Some(index) = index(WAIT_ABANDONED_0) {
+        Ok(WaitResult::Abandoned(index))
+    } else {
+        Err(Error::new(
+            api,
+            ErrorKind::Other,
+            format!(
+                "unexpected result 0x{:08X} of waiting for {} objects",
+                return_value, count
+            ),
+        ))
+    }
+}
+
+/// Convert a timeout to the milliseconds the WinAPI wait functions take.
+///
+/// `None` waits forever. Timeouts are rounded up to whole milliseconds, so that a short timeout
+/// doesn't turn into not waiting at all, and timeouts too long to express are capped just below
+/// `INFINITE`.
+pub fn timeout_millis(timeout: Option<Duration>) -> u32 {
+    match timeout {
+        None => INFINITE,
+        Some(timeout) => {
+            let millis = (timeout.as_nanos() + 999_999) / 1_000_000;
+            millis.min(u128::from(INFINITE - 1)) as u32
+        }
+    }
+}
+
+/// An object that can be waited for with [`wait_any`] and [`wait_all`], like a
+/// [`Semaphore`](crate::Semaphore) or an [`Event`](crate::Event).
+#[cfg(windows)]
+pub trait Waitable {
+    /// Get the handle that is waited for.
+    fn handle(&self) -> &Handle;
+}
+
+#[cfg(windows)]
+impl Waitable for Handle {
+    fn handle(&self) -> &Handle {
+        self
+    }
+}
+
+/// Wait until one of `objects` is signalled, for at most `timeout` or forever if it is `None`.
+///
+/// When several objects are signalled, the one with the lowest index is reported. There have to
+/// be between 1 and 64 objects.
+///
+/// This wraps
+/// [`WaitForMultipleObjects`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-waitformultipleobjects).
+#[cfg(windows)]
+pub fn wait_any(objects: &[&dyn Waitable], timeout: Option<Duration>) -> Result<WaitResult> {
+    wait_multiple(objects, false, timeout)
+}
+
+/// Wait until all of `objects` are signalled at the same time, for at most `timeout` or forever
+/// if it is `None`.
+///
+/// When the wait succeeds, the result is [`Signalled`](WaitResult::Signalled), whose index has no
+/// meaning here because all objects are signalled, or [`Abandoned`](WaitResult::Abandoned) with
+/// the index of an abandoned mutex among the objects.
+/// There have to be between 1 and 64 objects.
+///
+/// This wraps
+/// [`WaitForMultipleObjects`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-waitformultipleobjects).
+#[cfg(windows)]
+pub fn wait_all(objects: &[&dyn Waitable], timeout: Option<Duration>) -> Result<WaitResult> {
+    wait_multiple(objects, true, timeout)
+}
+
+#[cfg(windows)]
+fn wait_multiple(
+    objects: &[&dyn Waitable],
+    wait_all: bool,
+    timeout: Option<Duration>,
+) -> Result<WaitResult> {
+    if objects.is_empty() || objects.len() > MAXIMUM_WAIT_OBJECTS as usize {
+        return Err(Error::new(
+            "WaitForMultipleObjects",
+            ErrorKind::InvalidInput,
+            format!(
+                "can only wait for 1 to {} objects, not {}",
+                MAXIMUM_WAIT_OBJECTS,
+                objects.len()
+            ),
+        ));
+    }
+
+    let handles: Vec<HANDLE> = objects.iter().map(|object| **object.handle()).collect();
+    let return_value = unsafe {
+        WaitForMultipleObjects(
+            handles.len() as u32,
+            handles.as_ptr(),
+            BOOL::from(wait_all),
+            timeout_millis(timeout),
+        )
+    };
+
+    wait_result(
+        "WaitForMultipleObjects",
+        return_value,
+        handles.len(),
+        last_error,
+    )
+    .map_err(|e| {
+        e.with_argument("objects", &handles.len())
+            .with_argument("wait_all", &wait_all)
+            .with_argument("timeout", &timeout)
+    })
+}
+
+/// Wait until a single object is signalled, for at most `timeout` or forever if it is `None`.
+///
+/// This wraps
+/// [`WaitForSingleObject`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-waitforsingleobject).
+#[cfg(windows)]
+pub(crate) fn wait_one(handle: &Handle, timeout: Option<Duration>) -> Result<WaitResult> {
+    let return_value = unsafe { WaitForSingleObject(**handle, timeout_millis(timeout)) };
+    wait_result("WaitForSingleObject", return_value, 1, last_error)
+        .map_err(|e| e.with_handle(handle).with_argument("timeout", &timeout))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{timeout_millis, wait_result, WaitResult};
+    use crate::{ErrorKind, Result};
+
+    fn wait(return_value: u32, count: usize) -> Result<WaitResult> {
+        wait_result("WaitForMultipleObjects", return_value, count, || {
+            panic!("the last error is only read on failure")
+        })
+    }
+
+    #[test]
+    fn test_wait_result() {
+        assert_eq!(wait(0x0000, 1).unwrap(), WaitResult::Signalled(0));
+        assert_eq!(wait(0x0002, 3).unwrap(), WaitResult::Signalled(2));
+        assert_eq!(wait(0x0080, 1).unwrap(), WaitResult::Abandoned(0));
+        assert_eq!(wait(0x0081, 2).unwrap(), WaitResult::Abandoned(1));
+        assert_eq!(wait(0x0102, 2).unwrap(), WaitResult::TimedOut);
+
+        let error = wait_result("WaitForMultipleObjects", 0xFFFF_FFFF, 2, || 6).unwrap_err();
+        assert_eq!(error.api(), "WaitForMultipleObjects");
+        assert_eq!(error.code(), Some(6));
+        assert_eq!(error.kind(), ErrorKind::InvalidHandle);
+    }
+
+    #[test]
+    fn test_wait_result_out_of_range() {
+        for &(return_value, count) in &[(0x0001, 1), (0x0082, 2), (0x00C0, 64), (0x0103, 1)] {
+            let error = wait(return_value, count).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::Other);
+            assert_eq!(error.code(), None);
+        }
+    }
+
+    #[test]
+    fn test_timeout_millis() {
+        assert_eq!(timeout_millis(None), 0xFFFF_FFFF);
+        assert_eq!(timeout_millis(Some(Duration::from_secs(0))), 0);
+        assert_eq!(timeout_millis(Some(Duration::from_millis(50))), 50);
+        assert_eq!(timeout_millis(Some(Duration::from_micros(1))), 1);
+        assert_eq!(timeout_millis(Some(Duration::from_micros(1500))), 2);
+        assert_eq!(
+            timeout_millis(Some(Duration::from_secs(u64::MAX))),
+            0xFFFF_FFFE
+        );
+    }
+}
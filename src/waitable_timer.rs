@@ -0,0 +1,170 @@
+use std::convert::TryFrom;
+use std::mem::zeroed;
+use std::os::windows::io::{AsHandle, BorrowedHandle};
+use std::ptr;
+use std::time::Duration;
+
+use winapi::shared::minwindef::{BOOL, FALSE};
+use winapi::shared::ntdef::LARGE_INTEGER;
+use winapi::um::synchapi::{CancelWaitableTimer, CreateWaitableTimerW, SetWaitableTimer};
+
+use crate::wait::wait_one;
+use crate::{nonnull_handle_result, result, timeout_millis, Handle, Result, WaitResult, Waitable};
+
+/// A [Windows waitable timer](https://docs.microsoft.com/en-us/windows/win32/sync/waitable-timer-objects).
+///
+/// A timer becomes signalled when it is due, and again after every period of a periodic timer,
+/// which makes it useful for running something at a steady rate, like drawing frames. A
+/// synchronization timer is reset by the first wait that finds it signalled, a manual-reset
+/// timer stays signalled until it is [`set`](WaitableTimer::set) again.
+///
+/// Clones refer to the same timer.
+#[derive(Clone, Debug)]
+pub struct WaitableTimer(Handle);
+
+impl WaitableTimer {
+    /// Construct a new synchronization timer that is not running.
+    ///
+    /// This wraps
+    /// [`CreateWaitableTimerW`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-createwaitabletimerw).
+    pub fn new() -> Result<WaitableTimer> {
+        WaitableTimer::create(false)
+    }
+
+    /// Construct a new manual-reset timer that is not running.
+    ///
+    /// This wraps
+    /// [`CreateWaitableTimerW`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-createwaitabletimerw).
+    pub fn manual_reset() -> Result<WaitableTimer> {
+        WaitableTimer::create(true)
+    }
+
+    fn create(manual_reset: bool) -> Result<WaitableTimer> {
+        let handle = nonnull_handle_result("CreateWaitableTimerW", unsafe {
+            CreateWaitableTimerW(ptr::null_mut(), BOOL::from(manual_reset), ptr::null())
+        })
+        .map_err(|e| e.with_argument("manual_reset", &manual_reset))?;
+
+        let handle = unsafe { Handle::from_raw(handle) };
+        Ok(WaitableTimer(handle))
+    }
+
+    /// Start the timer, so that it is due after `due` and then every `period`, if given.
+    ///
+    /// The timer has a resolution of 100 nanoseconds for `due` and of a millisecond for
+    /// `period`, which is rounded up. Setting a running timer restarts it, and makes it not
+    /// signalled.
+    ///
+    /// This wraps
+    /// [`SetWaitableTimer`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-setwaitabletimer).
+    pub fn set(&self, due: Duration, period: Option<Duration>) -> Result<()> {
+        // A negative due time is relative to now, in units of 100 nanoseconds.
+        let mut due_time: LARGE_INTEGER = unsafe { zeroed() };
+        unsafe {
+            *due_time.QuadPart_mut() = -i64::try_from(due.as_nanos() / 100).unwrap_or(i64::MAX);
+        }
+
+        let period_millis = period.map_or(0, |period| {
+            i32::try_from(timeout_millis(Some(period))).unwrap_or(i32::MAX)
+        });
+
+        result("SetWaitableTimer", unsafe {
+            SetWaitableTimer(
+                *self.0,
+                &due_time,
+                period_millis,
+                None,
+                ptr::null_mut(),
+                FALSE,
+            )
+        })
+        .map_err(|e| {
+            e.with_handle(&self.0)
+                .with_argument("due", &due)
+                .with_argument("period", &period)
+        })
+    }
+
+    /// Stop the timer. This does not change whether it is signalled.
+    ///
+    /// This wraps
+    /// [`CancelWaitableTimer`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-cancelwaitabletimer).
+    pub fn cancel(&self) -> Result<()> {
+        result("CancelWaitableTimer", unsafe {
+            CancelWaitableTimer(*self.0)
+        })
+        .map_err(|e| e.with_handle(&self.0))
+    }
+
+    /// Wait until the timer is signalled.
+    pub fn wait(&self) -> Result<()> {
+        wait_one(&self.0, None)?;
+        Ok(())
+    }
+
+    /// Wait at most `timeout` until the timer is signalled, returning whether it was.
+    pub fn wait_timeout(&self, timeout: Duration) -> Result<bool> {
+        Ok(wait_one(&self.0, Some(timeout))? != WaitResult::TimedOut)
+    }
+
+    /// Access the underlying handle to the timer.
+    pub fn handle(&self) -> &Handle {
+        &self.0
+    }
+}
+
+impl AsHandle for WaitableTimer {
+    fn as_handle(&self) -> BorrowedHandle<'_> {
+        self.0.as_handle()
+    }
+}
+
+impl Waitable for WaitableTimer {
+    fn handle(&self) -> &Handle {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::WaitableTimer;
+    use crate::{wait_all, wait_any, Event, WaitResult, Waitable};
+
+    #[test]
+    fn test_periodic_timer() {
+        let timer = WaitableTimer::new().unwrap();
+        assert!(!timer.wait_timeout(Duration::from_millis(1)).unwrap());
+
+        timer
+            .set(Duration::from_millis(1), Some(Duration::from_millis(1)))
+            .unwrap();
+        timer.wait().unwrap();
+        timer.wait().unwrap();
+
+        timer.cancel().unwrap();
+    }
+
+    #[test]
+    fn test_wait_any_and_all() {
+        let timer = WaitableTimer::manual_reset().unwrap();
+        let event = Event::manual_reset().unwrap();
+        let objects: [&dyn Waitable; 2] = [&timer, &event];
+
+        event.set().unwrap();
+        assert_eq!(
+            wait_any(&objects, Some(Duration::from_secs(0))).unwrap(),
+            WaitResult::Signalled(1)
+        );
+        assert_eq!(
+            wait_all(&objects, Some(Duration::from_secs(0))).unwrap(),
+            WaitResult::TimedOut
+        );
+
+        timer.set(Duration::from_secs(0), None).unwrap();
+        assert_eq!(wait_all(&objects, None).unwrap(), WaitResult::Signalled(0));
+
+        assert!(wait_any(&[], None).is_err());
+    }
+}
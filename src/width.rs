@@ -0,0 +1,284 @@
+//! This module contains the logic for working out how many console cells text takes up.
+//!
+//! The console puts East Asian wide and fullwidth characters, and emoji that are displayed as
+//! pictures, in two cells, and combining marks in zero cells on top of the previous character.
+//! Everything else takes up one cell.
+
+use std::borrow::Cow;
+
+use unicode_width::UnicodeWidthChar;
+
+use super::{Coord, Size};
+
+/// U+FE0F VARIATION SELECTOR-16, which requests the emoji presentation of the previous character.
+const EMOJI_PRESENTATION_SELECTOR: char = '\u{FE0F}';
+
+/// The distance between the tab stops of the console.
+const TAB_WIDTH: i16 = 8;
+
+/// Get the number of cells a character takes up.
+///
+/// This is 2 for characters with an East Asian Width of Wide or Fullwidth, which includes all
+/// characters that are displayed as emoji by default, 0 for combining marks, other zero-width
+/// characters and control characters, and 1 for everything else.
+pub fn char_width(c: char) -> usize {
+    c.width().unwrap_or(0)
+}
+
+/// Get the number of cells a string takes up.
+///
+/// This is the sum of the [`char_width`] of its characters, except that a character which is
+/// followed by U+FE0F VARIATION SELECTOR-16, like the heart in `"❤\u{FE0F}"`, is displayed as
+/// an emoji and takes up two cells.
+pub fn str_width(s: &str) -> usize {
+    clusters(s).map(|(_, width)| width).sum()
+}
+
+/// Get the longest prefix of `s` that fits in `width` cells.
+///
+/// Combining marks are kept together with the character they belong to, and a wide character
+/// that would only fit halfway is left out.
+pub fn truncate_to_width(s: &str, width: usize) -> &str {
+    let (mut used, mut end) = (0, 0);
+    for (cluster, cluster_width) in clusters(s) {
+        if used + cluster_width > width {
+            break;
+        }
+        used += cluster_width;
+        end += cluster.len();
+    }
+    &s[..end]
+}
+
+/// Truncate `s` to `width` cells, then fill it up with spaces so that it takes up exactly
+/// `width` cells.
+pub fn pad_to_width(s: &str, width: usize) -> Cow<'_, str> {
+    let truncated = truncate_to_width(s, width);
+    let used = str_width(truncated);
+
+    if used == width {
+        Cow::Borrowed(truncated)
+    } else {
+        let mut padded = String::with_capacity(truncated.len() + width - used);
+        padded.push_str(truncated);
+        padded.extend(std::iter::repeat(' ').take(width - used));
+        Cow::Owned(padded)
+    }
+}
+
+/// Split `s` into lines of at most `width` cells each.
+///
+/// Lines are broken at every `'\n'` (which, like a preceding `'\r'`, is not part of the lines),
+/// and wherever the next character doesn't fit anymore, the way the console wraps text at the
+/// end of a row. If `width` is too small to fit even a single character, every character is put
+/// on its own line.
+pub fn wrap_to_width(s: &str, width: usize) -> Vec<&str> {
+    let mut lines = Vec::new();
+
+    for line in s.split('\n') {
+        let mut line = line.strip_suffix('\r').unwrap_or(line);
+        loop {
+            let mut head = truncate_to_width(line, width);
+            if head.is_empty() && !line.is_empty() {
+                head = clusters(line).next().map_or(line, |(cluster, _)| cluster);
+            }
+            lines.push(head);
+            line = &line[head.len()..];
+            if line.is_empty() {
+                break;
+            }
+        }
+    }
+
+    lines
+}
+
+/// Predict where the cursor ends up after writing `text` at `start` into a screen buffer of
+/// the given size, with the default output mode of the console
+/// (`ENABLE_PROCESSED_OUTPUT | ENABLE_WRAP_AT_EOL_OUTPUT`).
+///
+/// - Writing to the last column of a row moves the cursor to the start of the next row.
+/// - A wide character that doesn't fit in the rest of the row is written on the next row.
+/// - `'\n'` moves to the start of the next row, `'\r'` to the start of the current row, `'\t'`
+///   to the next tab stop and `'\x08'` (backspace) one column to the left. Other control
+///   characters don't move the cursor.
+/// - When the cursor moves past the last row, the buffer scrolls up, so the cursor stays on the
+///   last row.
+///
+/// A `start` outside of the buffer is clamped into it first, like the console does when setting
+/// the cursor position.
+pub fn cursor_after_write(start: Coord, text: &str, buffer_size: Size) -> Coord {
+    let (width, height) = (buffer_size.width.max(1), buffer_size.height.max(1));
+    let mut cursor = Coord::new(start.x.clamp(0, width - 1), start.y.clamp(0, height - 1));
+
+    let new_line = |cursor: &mut Coord| {
+        cursor.x = 0;
+        cursor.y = (cursor.y + 1).min(height - 1);
+    };
+
+    for (cluster, cluster_width) in clusters(text) {
+        match cluster.chars().next() {
+            Some('\n') => new_line(&mut cursor),
+            Some('\r') => cursor.x = 0,
+            Some('\t') => {
+                cursor.x = (cursor.x / TAB_WIDTH + 1).saturating_mul(TAB_WIDTH);
+                if cursor.x >= width {
+                    new_line(&mut cursor);
+                }
+            }
+            Some('\x08') => cursor.x = (cursor.x - 1).max(0),
+            _ if cluster_width == 0 => {}
+            _ => {
+                let cluster_width = cluster_width as i16;
+                if cluster_width > width - cursor.x && cursor.x > 0 {
+                    new_line(&mut cursor);
+                }
+                cursor.x += cluster_width;
+                if cursor.x >= width {
+                    new_line(&mut cursor);
+                }
+            }
+        }
+    }
+
+    cursor
+}
+
+/// Split a string into characters with the zero-width characters that follow them, together
+/// with the number of cells they take up.
+pub(crate) fn clusters(s: &str) -> impl Iterator<Item = (&str, usize)> {
+    let mut rest = s;
+    std::iter::from_fn(move || {
+        let mut chars = rest.char_indices();
+        let (_, first) = chars.next()?;
+        let mut width = char_width(first);
+        let mut end = rest.len();
+
+        for (i, c) in chars {
+            if first.is_control() || c.is_control() || char_width(c) != 0 {
+                end = i;
+                break;
+            }
+            if c == EMOJI_PRESENTATION_SELECTOR && width == 1 {
+                width = 2;
+            }
+        }
+
+        let (cluster, tail) = rest.split_at(end);
+        rest = tail;
+        Some((cluster, width))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        char_width, cursor_after_write, pad_to_width, str_width, truncate_to_width, wrap_to_width,
+    };
+    use crate::{Coord, Size};
+
+    #[test]
+    fn test_char_width() {
+        assert_eq!(char_width('a'), 1);
+        assert_eq!(char_width('\u{4E2D}'), 2); // 中
+        assert_eq!(char_width('\u{FF21}'), 2); // fullwidth A
+        assert_eq!(char_width('\u{1F600}'), 2); // 😀
+        assert_eq!(char_width('\u{0301}'), 0); // combining acute accent
+        assert_eq!(char_width('\u{200D}'), 0); // zero width joiner
+        assert_eq!(char_width('\n'), 0);
+    }
+
+    #[test]
+    fn test_str_width() {
+        assert_eq!(str_width("hello"), 5);
+        assert_eq!(str_width("e\u{0301}"), 1);
+        assert_eq!(str_width("\u{4E2D}\u{6587}"), 4);
+        assert_eq!(str_width("\u{2764}"), 1); // ❤ is text by default
+        assert_eq!(str_width("\u{2764}\u{FE0F}"), 2); // ❤️ is an emoji
+        assert_eq!(str_width("\u{1F600}\u{FE0F}"), 2);
+    }
+
+    #[test]
+    fn test_truncate_and_pad() {
+        assert_eq!(truncate_to_width("hello", 3), "hel");
+        assert_eq!(truncate_to_width("a\u{4E2D}b", 2), "a");
+        assert_eq!(truncate_to_width("ae\u{0301}b", 2), "ae\u{0301}");
+        assert_eq!(truncate_to_width("ab", 5), "ab");
+
+        assert_eq!(pad_to_width("ab", 4), "ab  ");
+        assert_eq!(pad_to_width("a\u{4E2D}b", 2), "a ");
+        assert_eq!(pad_to_width("abcd", 4), "abcd");
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap_to_width("abcdefg", 3), vec!["abc", "def", "g"]);
+        assert_eq!(
+            wrap_to_width("a\u{4E2D}\u{6587}", 2),
+            vec!["a", "\u{4E2D}", "\u{6587}"]
+        );
+        assert_eq!(wrap_to_width("ab\r\n\ncd", 5), vec!["ab", "", "cd"]);
+        assert_eq!(wrap_to_width("\u{4E2D}", 1), vec!["\u{4E2D}"]);
+        assert_eq!(wrap_to_width("", 3), vec![""]);
+    }
+
+    #[test]
+    fn test_cursor_after_write() {
+        let size = Size::new(10, 5);
+
+        assert_eq!(
+            cursor_after_write(Coord::new(0, 0), "abc", size),
+            Coord::new(3, 0)
+        );
+        assert_eq!(
+            cursor_after_write(Coord::new(8, 0), "ab", size),
+            Coord::new(0, 1)
+        );
+        // the wide character doesn't fit in the last column
+        assert_eq!(
+            cursor_after_write(Coord::new(9, 0), "\u{4E2D}", size),
+            Coord::new(2, 1)
+        );
+        assert_eq!(
+            cursor_after_write(Coord::new(3, 1), "e\u{0301}\r\n\tx", size),
+            Coord::new(9, 2)
+        );
+        assert_eq!(
+            cursor_after_write(Coord::new(0, 4), "scroll\n", size),
+            Coord::new(0, 4)
+        );
+        assert_eq!(
+            cursor_after_write(Coord::new(1, 0), "\x08\x08", size),
+            Coord::new(0, 0)
+        );
+    }
+
+    #[test]
+    fn test_cursor_after_write_extremes() {
+        // A start outside of the buffer is clamped into it.
+        assert_eq!(
+            cursor_after_write(Coord::new(i16::MAX, i16::MAX), "a", Size::new(10, 5)),
+            Coord::new(0, 4)
+        );
+        assert_eq!(
+            cursor_after_write(Coord::new(-3, -3), "a", Size::new(10, 5)),
+            Coord::new(1, 0)
+        );
+
+        // The largest buffer doesn't overflow at its last column and row.
+        let size = Size::new(i16::MAX, i16::MAX);
+        let last = Coord::new(i16::MAX - 1, i16::MAX - 1);
+        assert_eq!(
+            cursor_after_write(last, "\t", size),
+            Coord::new(0, i16::MAX - 1)
+        );
+        assert_eq!(
+            cursor_after_write(last, "\u{4E2D}", size),
+            Coord::new(2, i16::MAX - 1)
+        );
+        assert_eq!(
+            cursor_after_write(last, "\n", size),
+            Coord::new(0, i16::MAX - 1)
+        );
+    }
+}
@@ -0,0 +1,69 @@
+//! This module contains the Win32 constants that the platform-independent logic of this crate
+//! needs. They are defined here instead of taken from winapi, so that logic can be built and
+//! tested on every platform.
+
+// Win32 error codes.
+pub(crate) const ERROR_FILE_NOT_FOUND: u32 = 2;
+pub(crate) const ERROR_ACCESS_DENIED: u32 = 5;
+pub(crate) const ERROR_INVALID_HANDLE: u32 = 6;
+pub(crate) const ERROR_NOT_ENOUGH_MEMORY: u32 = 8;
+pub(crate) const ERROR_OUTOFMEMORY: u32 = 14;
+pub(crate) const ERROR_INVALID_PARAMETER: u32 = 87;
+pub(crate) const ERROR_BROKEN_PIPE: u32 = 109;
+#[cfg(any(windows, test))]
+pub(crate) const ERROR_INSUFFICIENT_BUFFER: u32 = 122;
+pub(crate) const ERROR_ALREADY_EXISTS: u32 = 183;
+pub(crate) const ERROR_TOO_MANY_POSTS: u32 = 298;
+
+/// The facility of an `HRESULT` that wraps a Win32 error code.
+#[cfg(any(windows, test))]
+pub(crate) const FACILITY_WIN32: u32 = 7;
+
+// Return values of the wait functions.
+pub(crate) const WAIT_OBJECT_0: u32 = 0x0000_0000;
+pub(crate) const WAIT_ABANDONED_0: u32 = 0x0000_0080;
+pub(crate) const WAIT_TIMEOUT: u32 = 0x0000_0102;
+pub(crate) const WAIT_FAILED: u32 = 0xFFFF_FFFF;
+pub(crate) const INFINITE: u32 = 0xFFFF_FFFF;
+
+// Return values of `GetFileType`.
+#[cfg(windows)]
+pub(crate) const FILE_TYPE_UNKNOWN: u32 = 0x0000;
+pub(crate) const FILE_TYPE_DISK: u32 = 0x0001;
+pub(crate) const FILE_TYPE_CHAR: u32 = 0x0002;
+pub(crate) const FILE_TYPE_PIPE: u32 = 0x0003;
+pub(crate) const FILE_TYPE_REMOTE: u32 = 0x8000;
+
+// Parts of the attributes of `UpdateProcThreadAttribute`.
+#[cfg(any(windows, test))]
+pub(crate) const PROC_THREAD_ATTRIBUTE_NUMBER: usize = 0x0000_ffff;
+#[cfg(any(windows, test))]
+pub(crate) const PROC_THREAD_ATTRIBUTE_THREAD: usize = 0x0001_0000;
+#[cfg(any(windows, test))]
+pub(crate) const PROC_THREAD_ATTRIBUTE_INPUT: usize = 0x0002_0000;
+#[cfg(any(windows, test))]
+pub(crate) const PROC_THREAD_ATTRIBUTE_ADDITIVE: usize = 0x0004_0000;
+
+/// The number of UTF-16 code units of the face name of `CONSOLE_FONT_INFOEX`.
+#[cfg(any(windows, test))]
+pub(crate) const LF_FACESIZE: usize = 32;
+/// The bit of the font family that marks a TrueType font.
+pub(crate) const TMPF_TRUETYPE: u32 = 0x04;
+
+// Control key states of key events.
+pub(crate) const RIGHT_ALT_PRESSED: u32 = 0x0001;
+pub(crate) const LEFT_ALT_PRESSED: u32 = 0x0002;
+pub(crate) const RIGHT_CTRL_PRESSED: u32 = 0x0004;
+pub(crate) const LEFT_CTRL_PRESSED: u32 = 0x0008;
+
+// Virtual-key codes.
+pub(crate) const VK_BACK: u16 = 0x08;
+pub(crate) const VK_RETURN: u16 = 0x0D;
+pub(crate) const VK_ESCAPE: u16 = 0x1B;
+pub(crate) const VK_END: u16 = 0x23;
+pub(crate) const VK_HOME: u16 = 0x24;
+pub(crate) const VK_LEFT: u16 = 0x25;
+pub(crate) const VK_UP: u16 = 0x26;
+pub(crate) const VK_RIGHT: u16 = 0x27;
+pub(crate) const VK_DOWN: u16 = 0x28;
+pub(crate) const VK_DELETE: u16 = 0x2E;